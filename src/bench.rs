@@ -0,0 +1,230 @@
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::days::Solver;
+use crate::parse::ParseError;
+
+/// How long to keep measuring a solution
+#[derive(Debug, Clone)]
+pub struct Settings {
+    pub min_iterations: usize,
+    pub max_iterations: usize,
+    /// Measuring stops once this is spent, if `min_iterations` have run
+    pub budget: Duration,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            min_iterations: 5,
+            max_iterations: 1000,
+            budget: Duration::from_secs(1),
+        }
+    }
+}
+
+/// Median timings of one solution, in nanoseconds
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Measurement {
+    pub day: u8,
+    pub part: u8,
+    pub variant: Option<String>,
+    pub iterations: usize,
+    pub generator_ns: u64,
+    pub solver_ns: u64,
+}
+
+impl Measurement {
+    pub fn total_ns(&self) -> u64 {
+        self.generator_ns + self.solver_ns
+    }
+
+    fn key(&self) -> (u8, u8, Option<&str>) {
+        (self.day, self.part, self.variant.as_deref())
+    }
+}
+
+/// A benchmark run, as written to disk
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BenchRun {
+    pub commit: Option<String>,
+    pub cpu: String,
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+    pub results: Vec<Measurement>,
+}
+
+impl BenchRun {
+    /// Starts an empty run, describing the current checkout and machine
+    pub fn new() -> Self {
+        BenchRun {
+            commit: git_commit(),
+            cpu: cpu_model(),
+            timestamp: SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()),
+            results: vec![],
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)? + "\n")?;
+        Ok(())
+    }
+
+    /// File name for this run in the history directory, sorting chronologically
+    pub fn file_name(&self) -> String {
+        match &self.commit {
+            Some(commit) => format!("{}-{}.json", self.timestamp, commit),
+            None => format!("{}.json", self.timestamp),
+        }
+    }
+}
+
+impl Default for BenchRun {
+    fn default() -> Self {
+        BenchRun::new()
+    }
+}
+
+fn git_commit() -> Option<String> {
+    let output = Command::new("git").args(["rev-parse", "--short", "HEAD"]).output().ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn cpu_model() -> String {
+    fs::read_to_string("/proc/cpuinfo")
+        .ok()
+        .and_then(|info| {
+            info.lines()
+                .find(|l| l.starts_with("model name"))
+                .and_then(|l| l.split_once(':'))
+                .map(|(_, model)| model.trim().to_string())
+        })
+        .unwrap_or_else(|| std::env::consts::ARCH.to_string())
+}
+
+/// Runs a solution repeatedly on `input`, keeping the median of each phase
+pub fn measure(solver: &dyn Solver, input: &str, settings: &Settings) -> Result<Measurement, ParseError> {
+    let (mut generator, mut solve) = (vec![], vec![]);
+    let start = Instant::now();
+    while generator.len() < settings.max_iterations
+        && (generator.len() < settings.min_iterations || start.elapsed() < settings.budget)
+    {
+        let timed = solver.run_timed(input)?;
+        generator.push(timed.generator);
+        solve.push(timed.solver);
+    }
+    Ok(Measurement {
+        day: solver.day(),
+        part: solver.part(),
+        variant: solver.variant().map(str::to_string),
+        iterations: generator.len(),
+        generator_ns: median(&mut generator),
+        solver_ns: median(&mut solve),
+    })
+}
+
+fn median(durations: &mut [Duration]) -> u64 {
+    durations.sort_unstable();
+    durations.get(durations.len() / 2).map_or(0, |d| d.as_nanos() as u64)
+}
+
+/// A solution that got slower than the baseline by more than the threshold
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Regression {
+    pub day: u8,
+    pub part: u8,
+    pub variant: Option<String>,
+    pub baseline_ns: u64,
+    pub current_ns: u64,
+}
+
+impl Regression {
+    /// Slowdown, in percent of the baseline
+    pub fn percent(&self) -> f64 {
+        (self.current_ns as f64 / self.baseline_ns.max(1) as f64 - 1.0) * 100.0
+    }
+}
+
+/// Compares total times against the baseline. Solutions missing from either run are ignored.
+pub fn compare(baseline: &BenchRun, current: &BenchRun, threshold_percent: f64) -> Vec<Regression> {
+    current
+        .results
+        .iter()
+        .filter_map(|m| {
+            let old = baseline.results.iter().find(|b| b.key() == m.key())?;
+            let limit = old.total_ns() as f64 * (1.0 + threshold_percent / 100.0);
+            (m.total_ns() as f64 > limit).then(|| Regression {
+                day: m.day,
+                part: m.part,
+                variant: m.variant.clone(),
+                baseline_ns: old.total_ns(),
+                current_ns: m.total_ns(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::days;
+
+    fn run(results: &[(u8, u64)]) -> BenchRun {
+        BenchRun {
+            commit: Some("abc1234".to_string()),
+            cpu: "test".to_string(),
+            timestamp: 0,
+            results: results
+                .iter()
+                .map(|&(day, ns)| Measurement {
+                    day,
+                    part: 1,
+                    variant: None,
+                    iterations: 1,
+                    generator_ns: ns / 4,
+                    solver_ns: ns - ns / 4,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn measures_each_phase() {
+        let solver = days::find(6, 2, None).unwrap();
+        let settings = Settings { min_iterations: 3, max_iterations: 3, budget: Duration::ZERO };
+        let measurement = measure(solver.as_ref(), "3,4,3,1,2", &settings).unwrap();
+        assert_eq!((measurement.day, measurement.part, measurement.iterations), (6, 2, 3));
+        assert!(measure(solver.as_ref(), "3,x", &settings).is_err());
+    }
+
+    #[test]
+    fn flags_regressions_beyond_threshold() {
+        let baseline = run(&[(1, 1000), (2, 1000), (3, 1000)]);
+        let current = run(&[(1, 1090), (2, 1200), (4, 5000)]);
+        let regressions = compare(&baseline, &current, 10.0);
+        assert_eq!(regressions.len(), 1);
+        assert_eq!((regressions[0].day, regressions[0].baseline_ns, regressions[0].current_ns), (2, 1000, 1200));
+        assert!((regressions[0].percent() - 20.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn json_roundtrip() {
+        let run = run(&[(1, 1000)]);
+        let json = serde_json::to_string(&run).unwrap();
+        assert!(json.contains("\"generator_ns\":250"));
+        assert_eq!(serde_json::from_str::<BenchRun>(&json).unwrap(), run);
+        assert_eq!(run.file_name(), "0-abc1234.json");
+    }
+}
@@ -0,0 +1,142 @@
+//! Arbitrary precision unsigned integers, for the answers that outgrow `u128`: day 3's ratings of wide reports and
+//! day 6's population over many days. Only what those need is implemented.
+
+use std::fmt::{self, Display};
+use std::ops::{Add, Mul};
+
+/// 32 bit limbs, least significant first, without trailing zeros
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct BigUint {
+    limbs: Vec<u32>,
+}
+
+impl BigUint {
+    pub fn zero() -> Self {
+        BigUint::default()
+    }
+
+    /// Reads bits, most significant first
+    pub fn from_bits(bits: &[bool]) -> Self {
+        let mut limbs = vec![0u32; bits.len().div_ceil(32)];
+        for (i, _) in bits.iter().rev().enumerate().filter(|(_, &bit)| bit) {
+            limbs[i / 32] |= 1 << (i % 32);
+        }
+        BigUint::from_limbs(limbs)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    fn from_limbs(mut limbs: Vec<u32>) -> Self {
+        while limbs.last() == Some(&0) {
+            limbs.pop();
+        }
+        BigUint { limbs }
+    }
+
+    /// Divides in place, returning the remainder
+    fn div_rem_small(&mut self, divisor: u32) -> u32 {
+        let mut remainder = 0u64;
+        for limb in self.limbs.iter_mut().rev() {
+            let current = remainder << 32 | *limb as u64;
+            *limb = (current / divisor as u64) as u32;
+            remainder = current % divisor as u64;
+        }
+        *self = BigUint::from_limbs(std::mem::take(&mut self.limbs));
+        remainder as u32
+    }
+}
+
+impl From<u128> for BigUint {
+    fn from(n: u128) -> Self {
+        BigUint::from_limbs((0..4).map(|i| (n >> (32 * i)) as u32).collect())
+    }
+}
+
+impl Add for &BigUint {
+    type Output = BigUint;
+
+    fn add(self, other: &BigUint) -> BigUint {
+        let (long, short) = if self.limbs.len() >= other.limbs.len() { (self, other) } else { (other, self) };
+        let mut limbs = Vec::with_capacity(long.limbs.len() + 1);
+        let mut carry = 0u64;
+        for (i, &limb) in long.limbs.iter().enumerate() {
+            let sum = limb as u64 + *short.limbs.get(i).unwrap_or(&0) as u64 + carry;
+            limbs.push(sum as u32);
+            carry = sum >> 32;
+        }
+        limbs.push(carry as u32);
+        BigUint::from_limbs(limbs)
+    }
+}
+
+impl Mul for &BigUint {
+    type Output = BigUint;
+
+    fn mul(self, other: &BigUint) -> BigUint {
+        if self.is_zero() || other.is_zero() {
+            return BigUint::zero();
+        }
+        let mut limbs = vec![0u32; self.limbs.len() + other.limbs.len()];
+        for (i, &x) in self.limbs.iter().enumerate() {
+            let mut carry = 0u64;
+            for (j, &y) in other.limbs.iter().enumerate() {
+                let sum = limbs[i + j] as u64 + x as u64 * y as u64 + carry;
+                limbs[i + j] = sum as u32;
+                carry = sum >> 32;
+            }
+            limbs[i + other.limbs.len()] = carry as u32;
+        }
+        BigUint::from_limbs(limbs)
+    }
+}
+
+impl Display for BigUint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Base 10^9 digits, least significant first
+        let mut n = self.clone();
+        let mut digits = vec![];
+        while !n.is_zero() {
+            digits.push(n.div_rem_small(1_000_000_000));
+        }
+        let mut digits = digits.iter().rev();
+        write!(f, "{}", digits.next().unwrap_or(&0))?;
+        digits.try_for_each(|d| write!(f, "{:09}", d))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arithmetic_matches_u128() {
+        let values = [0, 1, 999_999_999, 1_000_000_000, u32::MAX as u128, u64::MAX as u128 + 7, u128::MAX / 3];
+        for &a in &values {
+            assert_eq!(BigUint::from(a).to_string(), a.to_string());
+            for &b in &values {
+                let (x, y) = (BigUint::from(a), BigUint::from(b));
+                if let Some(sum) = a.checked_add(b) {
+                    assert_eq!(&x + &y, BigUint::from(sum));
+                }
+                if let Some(product) = a.checked_mul(b) {
+                    assert_eq!(&x * &y, BigUint::from(product));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn beyond_u128() {
+        let max = BigUint::from(u128::MAX);
+        assert_eq!((&max + &BigUint::from(1)).to_string(), "340282366920938463463374607431768211456");
+        assert_eq!(
+            (&max * &max).to_string(),
+            "115792089237316195423570985008687907852589419931798687112530834793049593217025"
+        );
+        let bits: Vec<bool> = "1".chars().chain("0".repeat(128).chars()).map(|c| c == '1').collect();
+        assert_eq!(BigUint::from_bits(&bits), &max + &BigUint::from(1));
+        assert_eq!(BigUint::from_bits(&[false, false]).to_string(), "0");
+    }
+}
@@ -0,0 +1,207 @@
+use std::fmt::{self, Display};
+use std::panic::{self, AssertUnwindSafe};
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::days::Solver;
+use crate::gen;
+
+/// How many random inputs to check each part on
+#[derive(Debug, Clone)]
+pub struct Settings {
+    pub cases: usize,
+    /// Inputs are generated with sizes between 1 and this, see [`gen`] for what it means for each day
+    pub max_size: usize,
+    pub seed: u64,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            cases: 100,
+            max_size: 20,
+            seed: 0,
+        }
+    }
+}
+
+/// Result of running one variant on an input. Invalid inputs and panics are compared like answers, a variant
+/// panicking where the others don't is a disagreement as well.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Answer(String),
+    Invalid(String),
+    Panic(String),
+}
+
+impl Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Outcome::Answer(answer) => write!(f, "{}", answer),
+            Outcome::Invalid(reason) => write!(f, "invalid input: {}", reason),
+            Outcome::Panic(message) => write!(f, "panicked: {}", message),
+        }
+    }
+}
+
+/// An input on which the variants of a part disagree, shrunk as much as possible
+#[derive(Debug, Clone)]
+pub struct Counterexample {
+    pub day: u8,
+    pub part: u8,
+    /// Seed and size of the generated input, before shrinking
+    pub seed: u64,
+    pub size: usize,
+    pub input: String,
+    pub outcomes: Vec<(Option<&'static str>, Outcome)>,
+}
+
+impl Display for Counterexample {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Day {} - Part {}: variants disagree (seed {}, size {}), on:", self.day, self.part, self.seed, self.size)?;
+        for line in self.input.lines() {
+            writeln!(f, "    {}", line)?;
+        }
+        for (variant, outcome) in &self.outcomes {
+            writeln!(f, "  {}: {}", variant.unwrap_or("default"), outcome)?;
+        }
+        Ok(())
+    }
+}
+
+/// Groups solutions by day and part, keeping the parts that have several variants
+pub fn variant_groups(solvers: Vec<Box<dyn Solver>>) -> Vec<Vec<Box<dyn Solver>>> {
+    let mut groups: Vec<Vec<Box<dyn Solver>>> = vec![];
+    for solver in solvers {
+        match groups.last_mut() {
+            Some(group) if (group[0].day(), group[0].part()) == (solver.day(), solver.part()) => group.push(solver),
+            _ => groups.push(vec![solver]),
+        }
+    }
+    groups.retain(|g| g.len() > 1);
+    groups
+}
+
+/// Runs every variant of a part on random inputs, returning the number of inputs checked or the first disagreement
+pub fn check(variants: &[Box<dyn Solver>], settings: &Settings) -> Result<usize, Counterexample> {
+    let (day, part) = (variants[0].day(), variants[0].part());
+    let mut rng = ChaCha8Rng::seed_from_u64(settings.seed ^ (day as u64) << 8 ^ part as u64);
+    for _ in 0..settings.cases {
+        let (seed, size) = (rng.gen(), rng.gen_range(1..=settings.max_size.max(1)));
+        let input = gen::generate(day, size, seed).expect("no generator for this day");
+        if agree(&run_all(variants, &input)) {
+            continue;
+        }
+        let input = shrink(variants, input);
+        return Err(Counterexample {
+            day,
+            part,
+            seed,
+            size,
+            outcomes: variants.iter().map(|v| v.variant()).zip(run_all(variants, &input)).collect(),
+            input,
+        });
+    }
+    Ok(settings.cases)
+}
+
+fn run_all(variants: &[Box<dyn Solver>], input: &str) -> Vec<Outcome> {
+    variants.iter().map(|v| run(v.as_ref(), input)).collect()
+}
+
+fn run(solver: &dyn Solver, input: &str) -> Outcome {
+    match panic::catch_unwind(AssertUnwindSafe(|| solver.run(input))) {
+        Ok(Ok(answer)) => Outcome::Answer(answer),
+        Ok(Err(e)) => Outcome::Invalid(e.to_string()),
+        Err(payload) => Outcome::Panic(
+            payload
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_default(),
+        ),
+    }
+}
+
+fn agree(outcomes: &[Outcome]) -> bool {
+    outcomes.windows(2).all(|w| w[0] == w[1])
+}
+
+/// Removes lines from the input while the variants still disagree, then items of a single remaining line. Chunks
+/// are halved when no chunk can be removed, down to single units.
+fn shrink(variants: &[Box<dyn Solver>], input: String) -> String {
+    let input = shrink_by(variants, input, "\n");
+    if input.contains('\n') {
+        input
+    } else {
+        shrink_by(variants, input, ",")
+    }
+}
+
+fn shrink_by(variants: &[Box<dyn Solver>], input: String, sep: &str) -> String {
+    let mut units: Vec<&str> = input.split(sep).collect();
+    let mut chunk = units.len().div_ceil(2);
+    while chunk > 0 {
+        let mut removed = false;
+        let mut start = 0;
+        while start < units.len() && units.len() > 1 {
+            let candidate: Vec<&str> = units[..start].iter().chain(units.iter().skip(start + chunk)).copied().collect();
+            if !candidate.is_empty() && !agree(&run_all(variants, &candidate.join(sep))) {
+                units = candidate;
+                removed = true;
+            } else {
+                start += chunk;
+            }
+        }
+        if !removed {
+            chunk /= 2;
+        }
+    }
+    units.join(sep)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::days::{self, Solution};
+    use crate::parse::{Input, ParseError};
+
+    fn depths(input: &str) -> Result<Vec<i32>, ParseError> {
+        let input = Input::new(1, input);
+        input.lines().map(|l| input.parse(l)).collect()
+    }
+
+    fn count(depths: &[i32]) -> usize {
+        depths.len()
+    }
+
+    fn count_skipping_sevens(depths: &[i32]) -> usize {
+        depths.iter().filter(|&&d| d % 7 != 0).count()
+    }
+
+    #[test]
+    fn registered_variants_agree() {
+        let settings = Settings { cases: 10, max_size: 10, seed: 1 };
+        let groups = variant_groups(days::registry());
+        assert!(groups.iter().any(|g| (g[0].day(), g[0].part(), g.len()) == (6, 1, 4)));
+        for group in groups {
+            if let Err(counterexample) = check(&group, &settings) {
+                panic!("{}", counterexample);
+            }
+        }
+    }
+
+    #[test]
+    fn shrinks_to_minimal_counterexample() {
+        let variants = [
+            Solution::boxed(1, 1, None, depths, count),
+            Solution::boxed(1, 1, Some("buggy"), depths, count_skipping_sevens),
+        ];
+        let counterexample = check(&variants, &Settings::default()).unwrap_err();
+        assert_eq!(counterexample.input.lines().count(), 1);
+        assert_eq!(counterexample.input.parse::<i32>().unwrap() % 7, 0);
+        assert_eq!(counterexample.outcomes[0], (None, Outcome::Answer("1".to_string())));
+        assert_eq!(counterexample.outcomes[1], (Some("buggy"), Outcome::Answer("0".to_string())));
+    }
+}
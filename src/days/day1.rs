@@ -1,23 +1,189 @@
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+use std::io::BufRead;
 use itertools::Itertools;
+use serde::Serialize;
+use crate::parse::{Input, ParseError};
+use super::{Solution, Solver};
+
+/// Counts increases of the sum of a sliding window of measurements, keeping only the last `size` of them.
+///
+/// Two consecutive windows share all but their first and last measurements, so the sum increases exactly when the
+/// new measurement is larger than the one that left the window.
+#[derive(Clone, Debug)]
+pub struct SlidingWindow {
+    size: usize,
+    recent: VecDeque<i64>,
+    pushed: usize,
+}
+
+impl SlidingWindow {
+    pub fn new(size: usize) -> Self {
+        assert!(size > 0, "windows must hold at least one measurement");
+        SlidingWindow { size, recent: VecDeque::with_capacity(size + 1), pushed: 0 }
+    }
+
+    /// Adds the next measurement. If it completes a window larger than the previous one, returns that window's
+    /// index, which is also the index of its first measurement.
+    pub fn push(&mut self, depth: i64) -> Option<usize> {
+        self.pushed += 1;
+        self.recent.push_back(depth);
+        if self.recent.len() <= self.size {
+            return None;
+        }
+        let left = self.recent.pop_front().unwrap();
+        (depth > left).then_some(self.pushed - self.size)
+    }
+}
+
+/// Runs a [`SlidingWindow`] over one measurement per line, calling `on_increase` with the index of each window larger
+/// than the previous one. Lines are read one at a time, so the input can be arbitrarily large. Returns the number of
+/// increases.
+pub fn stream_increases(
+    mut reader: impl BufRead,
+    size: usize,
+    mut on_increase: impl FnMut(usize),
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let mut window = SlidingWindow::new(size);
+    let (mut line, mut number, mut count) = (String::new(), 0, 0);
+    while reader.read_line(&mut line)? > 0 {
+        number += 1;
+        let text = line.trim_end_matches(['\n', '\r']);
+        let depth = Input::new(1, text).parse(text).map_err(|e| ParseError { line: number, ..e })?;
+        if let Some(index) = window.push(depth) {
+            on_increase(index);
+            count += 1;
+        }
+        line.clear();
+    }
+    Ok(count)
+}
+
+fn count_increases(depths: &[i32], size: usize) -> usize {
+    let mut window = SlidingWindow::new(size);
+    depths.iter().filter_map(|&d| window.push(d as i64)).count()
+}
+
+/// Consecutive measurements, from index `start`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct Run {
+    pub start: usize,
+    pub len: usize,
+}
+
+impl Run {
+    fn at(start: usize) -> Self {
+        Run { start, len: 1 }
+    }
+
+    fn extended(self) -> Self {
+        Run { len: self.len + 1, ..self }
+    }
+}
+
+/// The change between a measurement, at `index`, and the previous one
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct Jump {
+    pub index: usize,
+    pub from: i32,
+    pub to: i32,
+    pub change: i64,
+}
+
+/// Trends of a depth series. Runs are at least two measurements long, the first one wins between equally long ones.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct DepthReport {
+    pub measurements: usize,
+    pub increases: usize,
+    pub decreases: usize,
+    pub longest_increase: Option<Run>,
+    pub longest_decrease: Option<Run>,
+    /// Runs of equal measurements
+    pub plateaus: Vec<Run>,
+    pub largest_rise: Option<Jump>,
+    pub largest_drop: Option<Jump>,
+    /// Changes larger than this, either way, are reported as anomalies
+    pub anomaly_threshold: u32,
+    pub anomalies: Vec<Jump>,
+}
+
+impl DepthReport {
+    pub fn parse(input: &str, anomaly_threshold: u32) -> Result<Self, ParseError> {
+        Ok(DepthReport::new(&get_nums(input)?, anomaly_threshold))
+    }
+
+    pub fn new(depths: &[i32], anomaly_threshold: u32) -> Self {
+        let mut report = DepthReport { measurements: depths.len(), anomaly_threshold, ..DepthReport::default() };
+        let (mut rising, mut falling, mut flat) = (Run::at(0), Run::at(0), Run::at(0));
+        for (i, (&from, &to)) in depths.iter().tuple_windows().enumerate() {
+            let index = i + 1;
+            let jump = Jump { index, from, to, change: to as i64 - from as i64 };
+            let order = to.cmp(&from);
+            rising = if order == Ordering::Greater { rising.extended() } else { Run::at(index) };
+            falling = if order == Ordering::Less { falling.extended() } else { Run::at(index) };
+            match order {
+                Ordering::Greater => {
+                    report.increases += 1;
+                    longest(&mut report.longest_increase, rising);
+                    if report.largest_rise.is_none_or(|r| jump.change > r.change) {
+                        report.largest_rise = Some(jump);
+                    }
+                }
+                Ordering::Less => {
+                    report.decreases += 1;
+                    longest(&mut report.longest_decrease, falling);
+                    if report.largest_drop.is_none_or(|d| jump.change < d.change) {
+                        report.largest_drop = Some(jump);
+                    }
+                }
+                Ordering::Equal => {}
+            }
+            if order == Ordering::Equal {
+                flat = flat.extended();
+            } else {
+                if flat.len > 1 {
+                    report.plateaus.push(flat);
+                }
+                flat = Run::at(index);
+            }
+            if jump.change.unsigned_abs() > anomaly_threshold as u64 {
+                report.anomalies.push(jump);
+            }
+        }
+        if flat.len > 1 {
+            report.plateaus.push(flat);
+        }
+        report
+    }
+}
+
+fn longest(best: &mut Option<Run>, run: Run) {
+    if best.is_none_or(|b| run.len > b.len) {
+        *best = Some(run);
+    }
+}
 
 #[aoc_generator(day1)]
-fn get_nums(input: &str) -> Vec<i32> {
-    input.lines().map(|l| l.parse().unwrap()).collect()
+fn get_nums(input: &str) -> Result<Vec<i32>, ParseError> {
+    let input = Input::new(1, input);
+    input.lines().map(|l| input.parse(l)).collect()
 }
 
 #[aoc(day1, part1)]
 pub fn part1(input: &[i32]) -> usize {
-    input.windows(2).filter(|arr| arr[0] < arr[1]).count()
+    count_increases(input, 1)
 }
 
 #[aoc(day1, part2)]
 pub fn part2(input: &[i32]) -> usize {
-    input
-        .windows(3)
-        .map(|arr| arr.iter().sum::<i32>())
-        .tuple_windows()
-        .filter(|(a, b)| a < b)
-        .count()
+    count_increases(input, 3)
+}
+
+pub(super) fn solutions() -> Vec<Box<dyn Solver>> {
+    vec![
+        Solution::boxed(1, 1, None, get_nums, part1),
+        Solution::boxed(1, 2, None, get_nums, part2),
+    ]
 }
 
 #[cfg(test)]
@@ -27,12 +193,57 @@ mod tests {
     #[test]
     fn part1_example() {
         let input = example!(199 200 208 210 200 207 240 269 260 263);
-        assert_eq!(part1(&get_nums(input)), 7);
+        assert_eq!(part1(&get_nums(input).unwrap()), 7);
     }
 
     #[test]
     fn part2_example() {
         let input = example!(199 200 208 210 200 207 240 269 260 263);
-        assert_eq!(part2(&get_nums(input)), 5);
+        assert_eq!(part2(&get_nums(input).unwrap()), 5);
+    }
+
+    #[test]
+    fn any_window_size() {
+        let depths = get_nums(example!(199 200 208 210 200 207 240 269 260 263)).unwrap();
+        let naive = |size: usize| {
+            let sums: Vec<i32> = depths.windows(size).map(|w| w.iter().sum()).collect();
+            sums.windows(2).filter(|s| s[0] < s[1]).count()
+        };
+        for size in 1..=9 {
+            assert_eq!(count_increases(&depths, size), naive(size), "window of {}", size);
+        }
+        assert_eq!(count_increases(&depths, 10), 0);
+    }
+
+    #[test]
+    fn reports_trends() {
+        let report = DepthReport::parse(example!(199 200 208 210 200 207 240 269 260 263), 20).unwrap();
+        assert_eq!((report.measurements, report.increases, report.decreases), (10, 7, 2));
+        assert_eq!(report.longest_increase, Some(Run { start: 0, len: 4 }));
+        assert_eq!(report.longest_decrease, Some(Run { start: 3, len: 2 }));
+        assert_eq!(report.largest_rise, Some(Jump { index: 6, from: 207, to: 240, change: 33 }));
+        assert_eq!(report.largest_drop.map(|d| (d.index, d.change)), Some((4, -10)));
+        assert_eq!(report.anomalies.iter().map(|a| a.index).collect::<Vec<_>>(), [6, 7]);
+        assert!(report.plateaus.is_empty());
+
+        let report = DepthReport::parse(example!(5 5 5 4 4 3 9 9), 5).unwrap();
+        assert_eq!(report.plateaus, [Run { start: 0, len: 3 }, Run { start: 3, len: 2 }, Run { start: 6, len: 2 }]);
+        assert_eq!(report.longest_decrease, Some(Run { start: 2, len: 2 }));
+        assert_eq!(report.anomalies.len(), 1);
+        let json = serde_json::to_string(&report).unwrap();
+        assert!(json.contains(r#""largest_rise":{"index":6,"from":3,"to":9,"change":6}"#));
+        assert!(json.contains(r#""longest_increase":{"start":5,"len":2}"#));
+    }
+
+    #[test]
+    fn streams_indices() {
+        let input = example!(199 200 208 210 200 207 240 269 260 263);
+        let mut indices = vec![];
+        assert_eq!(stream_increases(input.as_bytes(), 3, |i| indices.push(i)).unwrap(), 5);
+        assert_eq!(indices, [1, 4, 5, 6, 7]);
+
+        let error = stream_increases("199\r\n200\r\n2O8\r\n".as_bytes(), 1, |_| {}).unwrap_err();
+        let error = error.downcast::<ParseError>().unwrap();
+        assert_eq!((error.line, error.token.as_str()), (3, "2O8"));
     }
 }
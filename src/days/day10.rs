@@ -1,8 +1,21 @@
 use std::collections::VecDeque;
+use crate::parse::{Input, ParseError};
+use super::{Solution, Solver};
 
-#[aoc_generator(day10)]
-fn parse(input: &str) -> Vec<String> {
-    input.lines().map(|s| s.to_string()).collect()
+#[aoc_generator(day10, part1)]
+fn parse(input: &str) -> Result<Vec<String>, ParseError> {
+    let input = Input::new(10, input);
+    input.lines().map(|s| input.chars(s, "()[]{}<>").map(|c| c.into_iter().collect())).collect()
+}
+
+/// Also checks that a line is incomplete, part 2 has no middle score otherwise
+#[aoc_generator(day10, part2)]
+fn parse_incomplete(input: &str) -> Result<Vec<String>, ParseError> {
+    let lines = parse(input)?;
+    if !lines.iter().any(|l| completion(l).is_some()) {
+        return Err(Input::new(10, input).missing(input, "an incomplete line"));
+    }
+    Ok(lines)
 }
 
 const OPENERS: [char; 4] = ['(', '[', '{', '<'];
@@ -35,21 +48,7 @@ fn part1(input: &[String]) -> usize {
 #[aoc(day10, part2)]
 fn part2(input: &[String]) -> usize {
     let mut scores = input.iter().filter_map(|s| {
-        let mut levels = VecDeque::with_capacity(s.len());
-        for char in s.chars() {
-            let index = OPENERS.iter().position(|&c| c == char);
-            if let Some(index) = index {
-                levels.push_back(index);
-                continue;
-            }
-            levels.pop_back().and_then(|i| (CLOSERS[i] == char).then(|| ()))?;
-        }
-
-        if levels.is_empty() {
-            return None;
-        }
-
-        Some(levels.into_iter().rev().map(|i| match CLOSERS[i] {
+        Some(completion(s)?.into_iter().rev().map(|i| match CLOSERS[i] {
             ')' => 1,
             ']' => 2,
             '}' => 3,
@@ -61,6 +60,27 @@ fn part2(input: &[String]) -> usize {
     scores[scores.len() / 2]
 }
 
+/// The indices of the chunks left open by an incomplete line, `None` if it is corrupted or complete
+fn completion(s: &str) -> Option<VecDeque<usize>> {
+    let mut levels = VecDeque::with_capacity(s.len());
+    for char in s.chars() {
+        let index = OPENERS.iter().position(|&c| c == char);
+        if let Some(index) = index {
+            levels.push_back(index);
+            continue;
+        }
+        levels.pop_back().and_then(|i| (CLOSERS[i] == char).then(|| ()))?;
+    }
+    (!levels.is_empty()).then_some(levels)
+}
+
+pub(super) fn solutions() -> Vec<Box<dyn Solver>> {
+    vec![
+        Solution::boxed(10, 1, None, parse, part1),
+        Solution::boxed(10, 2, None, parse_incomplete, part2),
+    ]
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -77,7 +97,7 @@ mod tests {
 [<(<(<(<{}))><([]([]()
 <{([([[(<>()){}]>(<<{{
 <{([{{}}[<[[[<>{}]]]>[]]"#;
-        assert_eq!(part1(&parse(input)), 26397);
+        assert_eq!(part1(&parse(input).unwrap()), 26397);
     }
 
     #[test]
@@ -92,6 +112,19 @@ mod tests {
 [<(<(<(<{}))><([]([]()
 <{([([[(<>()){}]>(<<{{
 <{([{{}}[<[[[<>{}]]]>[]]"#;
-        assert_eq!(part2(&parse(input)), 288957);
+        assert_eq!(part2(&parse_incomplete(input).unwrap()), 288957);
+    }
+
+    #[test]
+    fn malformed_input() {
+        let err = parse_incomplete("").err().unwrap();
+        assert_eq!((err.line, err.reason.as_str()), (1, "expected an incomplete line"));
+        let err = parse_incomplete("()\n(]").err().unwrap();
+        assert_eq!((err.line, err.column, err.reason.as_str()), (2, 3, "expected an incomplete line"));
+        // Part 1 scores the corrupted lines without needing an incomplete one
+        assert_eq!(part1(&parse("()\n(]").unwrap()), 57);
+        let err = parse("(\n(x").err().unwrap();
+        assert_eq!((err.line, err.column, err.token.as_str()), (2, 2, "x"));
+        assert_eq!(part2(&parse_incomplete("()\n(]\n[(").unwrap()), 7);
     }
 }
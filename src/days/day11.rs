@@ -1,116 +1,100 @@
 use fxhash::{FxBuildHasher, FxHashSet};
-use std::cell::Cell;
+use crate::grid::{Grid, Neighbours};
+use crate::parse::{Input, ParseError};
+use crate::sim::Simulation;
+use super::{Solution, Solver};
 
-#[derive(Clone)]
-struct Grid {
-    inner: Vec<Cell<u8>>,
-    line_len: isize,
+/// The octopus grid, flashing at each step
+#[derive(Clone, Debug)]
+pub struct Octopuses {
+    grid: Grid<u8>,
+    generation: usize,
 }
 
-impl Grid {
-    pub fn from(raw: Vec<Vec<u8>>) -> Self {
-        Grid {
-            line_len: raw[0].len() as isize,
-            inner: raw
-                .into_iter()
-                .flat_map(|v| v.into_iter())
-                .map(Cell::new)
-                .collect(),
-        }
+impl Octopuses {
+    pub fn parse(input: &str) -> Result<Self, ParseError> {
+        parse(input).map(Octopuses::new)
     }
 
-    pub fn window(&self, center_pos: isize) -> [Option<(usize, &Cell<u8>)>; 9] {
-        let mut window = [None, None, None, None, None, None, None, None, None];
-
-        let center_x = center_pos % self.line_len;
-        let center_z = center_pos / self.line_len;
-
-        let mut i = 0;
-        for x in -1isize..=1 {
-            for z in -1isize..=1 {
-                let x = center_x + x;
-                let z = center_z + z;
-                if x < 0 || z < 0 || x >= self.line_len {
-                    window[i] = None;
-                    continue;
-                }
-                let index = (x + z * self.line_len) as usize;
-                window[i] = self.inner.get(index).map(|c| (index, c));
-                i += 1;
-            }
-        }
+    fn new(grid: Grid<u8>) -> Self {
+        Octopuses { grid, generation: 0 }
+    }
+}
+
+impl Simulation for Octopuses {
+    /// Number of octopuses that flashed
+    type Step = usize;
+    /// Energy level of each octopus
+    type Snapshot = Grid<u8>;
+
+    fn step(&mut self) -> usize {
+        self.generation += 1;
+        step(&mut self.grid)
+    }
 
-        window
+    fn generation(&self) -> usize {
+        self.generation
+    }
+
+    fn snapshot(&self) -> Grid<u8> {
+        self.grid.clone()
     }
 }
 
 #[aoc_generator(day11)]
-fn parse(input: &str) -> Grid {
-    Grid::from(
-        input
-            .lines()
-            .map(|s| s.chars().map(|c| c.to_digit(10).unwrap() as u8).collect())
-            .collect(),
-    )
+fn parse(input: &str) -> Result<Grid<u8>, ParseError> {
+    let input = Input::new(11, input);
+    Grid::parse(&input, input.text(), "0123456789", |c| c.to_digit(10).unwrap() as u8)
 }
 
 #[aoc(day11, part1)]
-fn part1(input: &Grid) -> usize {
-    let mut flashes = 0;
-    for _ in 0..100 {
-        for octopus in &input.inner {
-            octopus.set(octopus.get() + 1);
-        }
-        let mut flashed =
-            FxHashSet::with_capacity_and_hasher(input.inner.len(), FxBuildHasher::default());
-        for (i, _) in input.inner.iter().enumerate().filter(|(_, v)| v.get() > 9) {
-            flash(i, input, &mut flashed);
-        }
-        for pos in &flashed {
-            input.inner[*pos].set(0);
-        }
-        flashes += flashed.len();
-    }
-    flashes
+fn part1(input: &Grid<u8>) -> usize {
+    let mut octopuses = Octopuses::new(input.clone());
+    (0..100).map(|_| octopuses.step()).sum()
 }
 
 #[aoc(day11, part2)]
-fn part2(input: &Grid) -> usize {
-    let mut i = 1;
-    loop {
-        for octopus in &input.inner {
-            octopus.set(octopus.get() + 1);
-        }
-        let mut flashed =
-            FxHashSet::with_capacity_and_hasher(input.inner.len(), FxBuildHasher::default());
-        for (i, _) in input.inner.iter().enumerate().filter(|(_, v)| v.get() > 9) {
-            flash(i, input, &mut flashed);
-        }
-        for pos in &flashed {
-            input.inner[*pos].set(0);
-        }
-        if flashed.len() == input.inner.len() {
-            return i;
-        }
-        i += 1;
+fn part2(input: &Grid<u8>) -> usize {
+    let mut octopuses = Octopuses::new(input.clone());
+    while octopuses.step() != input.len() {}
+    octopuses.generation()
+}
+
+/// Runs a single step, returning the number of octopuses that flashed
+fn step(grid: &mut Grid<u8>) -> usize {
+    for octopus in grid.cells_mut() {
+        *octopus += 1;
+    }
+    let mut flashed = FxHashSet::with_capacity_and_hasher(grid.len(), FxBuildHasher::default());
+    for pos in grid.positions() {
+        flash(pos, grid, &mut flashed);
     }
+    for pos in &flashed {
+        grid[*pos] = 0;
+    }
+    flashed.len()
 }
 
-fn flash(pos: usize, grid: &Grid, flashed: &mut FxHashSet<usize>) {
-    if flashed.contains(&pos) || grid.inner[pos].get() <= 9 {
+fn flash(pos: (usize, usize), grid: &mut Grid<u8>, flashed: &mut FxHashSet<(usize, usize)>) {
+    if flashed.contains(&pos) || grid[pos] <= 9 {
         return;
     }
     flashed.insert(pos);
-    for (new_pos, v) in grid.window(pos as isize).into_iter().flatten() {
-        if new_pos != pos {
-            v.set(v.get() + 1);
-            if v.get() > 9 && !flashed.contains(&new_pos) {
-                flash(new_pos, grid, flashed);
-            }
+    for new_pos in grid.neighbours(pos, Neighbours::Eight) {
+        grid[new_pos] += 1;
+        if grid[new_pos] > 9 && !flashed.contains(&new_pos) {
+            flash(new_pos, grid, flashed);
         }
     }
 }
 
+pub(super) fn solutions() -> Vec<Box<dyn Solver>> {
+    vec![
+        Solution::boxed(11, 1, None, parse, part1),
+        Solution::boxed(11, 2, None, parse, part2),
+    ]
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -127,7 +111,7 @@ mod tests {
 6882881134
 4846848554
 5283751526"#;
-        assert_eq!(part1(&parse(input)), 1656);
+        assert_eq!(part1(&parse(input).unwrap()), 1656);
     }
 
     #[test]
@@ -142,6 +126,13 @@ mod tests {
 6882881134
 4846848554
 5283751526"#;
-        assert_eq!(part2(&parse(input)), 195);
+        assert_eq!(part2(&parse(input).unwrap()), 195);
+    }
+
+    #[test]
+    fn parts_share_input() {
+        let grid = parse("11111\n19991\n19191\n19991\n11111").unwrap();
+        assert_eq!(part1(&grid), part1(&grid));
+        assert_eq!(step(&mut grid.clone()), 9);
     }
 }
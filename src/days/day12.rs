@@ -1,11 +1,14 @@
 use fxhash::{FxHashMap, FxHashSet};
-use itertools::Itertools;
+use crate::parse::{Input, ParseError};
+use super::{Solution, Solver};
 
 #[aoc_generator(day12)]
-fn parse(input: &str) -> FxHashMap<i64, FxHashSet<i64>> {
+fn parse(input: &str) -> Result<FxHashMap<i64, FxHashSet<i64>>, ParseError> {
+    let input = Input::new(12, input);
     let mut map = FxHashMap::default();
     for line in input.lines() {
-        let (start, end) = line.split('-').map(get_cave_id).collect_tuple().unwrap();
+        let (start, end) = input.split_once(line, "-")?;
+        let (start, end) = (get_cave_id(&input, start)?, get_cave_id(&input, end)?);
         map.entry(start).or_insert_with(FxHashSet::default).insert(end);
     }
     for (k, v) in map.clone().into_iter() {
@@ -13,7 +16,7 @@ fn parse(input: &str) -> FxHashMap<i64, FxHashSet<i64>> {
             map.entry(*cave).or_insert_with(FxHashSet::default).insert(k);
         }
     }
-    map
+    Ok(map)
 }
 
 #[aoc(day12, part1)]
@@ -57,16 +60,26 @@ fn traverse(paths: &FxHashMap<i64, FxHashSet<i64>>, current_cave: i64,
     }
 }
 
-fn get_cave_id(cave: &str) -> i64 {
-    match cave {
+fn get_cave_id(input: &Input, cave: &str) -> Result<i64, ParseError> {
+    Ok(match cave {
         "start" => 0,
         "end" => i64::MAX,
         s => {
+            if s.is_empty() || !s.chars().all(|c| c.is_ascii_alphabetic()) {
+                return Err(input.error(s, "expected a cave name"));
+            }
             let lowercase = s.chars().all(|c| c.is_lowercase());
             let num: i64 = s.chars().map(|c| c as u16 as i64).product();
             if lowercase { -num } else { num }
         }
-    }
+    })
+}
+
+pub(super) fn solutions() -> Vec<Box<dyn Solver>> {
+    vec![
+        Solution::boxed(12, 1, None, parse, part1),
+        Solution::boxed(12, 2, None, parse, part2),
+    ]
 }
 
 #[cfg(test)]
@@ -85,7 +98,7 @@ HN-end
 kj-sa
 kj-HN
 kj-dc";
-        assert_eq!(part1(&parse(input)), 19);
+        assert_eq!(part1(&parse(input).unwrap()), 19);
     }
 
     #[test]
@@ -100,6 +113,6 @@ HN-end
 kj-sa
 kj-HN
 kj-dc";
-        assert_eq!(part2(&parse(input)), 103);
+        assert_eq!(part2(&parse(input).unwrap()), 103);
     }
 }
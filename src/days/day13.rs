@@ -1,12 +1,31 @@
 use fxhash::FxHashSet;
-use itertools::Itertools;
+use crate::grid::Grid as Map;
+use crate::parse::{Input, ParseError};
+use super::{Solution, Solver};
 
 #[derive(Clone)]
-struct Paper {
+pub struct Paper {
     grid: Grid,
     folds: Vec<Fold>
 }
 
+impl Paper {
+    pub fn parse(input: &str) -> Result<Self, ParseError> {
+        parse(input)
+    }
+
+    /// The dots of the transparent paper before any fold, then after each of them
+    pub fn fold_states(&self) -> Vec<Map<bool>> {
+        let mut grid = self.grid.clone();
+        let mut states = vec![grid.to_map()];
+        for fold in &self.folds {
+            grid.fold(fold);
+            states.push(grid.to_map());
+        }
+        states
+    }
+}
+
 #[derive(Clone)]
 enum Fold {
     Up(usize),
@@ -53,35 +72,56 @@ impl Grid {
         self.inner.dedup();
     }
 
-    fn print(self) -> String {
+    /// Whether each position of the smallest rectangle holding every dot has one
+    fn to_map(&self) -> Map<bool> {
         let max_x = self.inner.iter().map(|(x, _)| x).copied().max().unwrap();
         let max_y = self.inner.iter().map(|(_, y)| y).copied().max().unwrap();
-        let set: FxHashSet<_> = self.inner.into_iter().collect();
-        let mut buf = String::with_capacity(1 + (max_x + 1) * (max_y + 1) + max_y + 1);
-        buf += "\n";
-        for y in 0..=max_y {
-            for x in 0..=max_x {
-                buf += if set.contains(&(x, y)) {"#"} else {" "};
-            }
-            buf += "\n";
-        }
-        buf
+        let set: FxHashSet<_> = self.inner.iter().copied().collect();
+        Map::from_fn(max_x + 1, max_y + 1, |pos| set.contains(&pos))
+    }
+
+    fn print(self) -> String {
+        "\n".to_string() + &self.to_map().render(|&dot| if dot {'#'} else {' '})
     }
 }
 
 #[aoc_generator(day13)]
-fn parse(input: &str) -> Paper {
-    let (coords, folds) = input.split("\n\n").collect_tuple().unwrap();
-    let coords = coords.lines().map(|l| l.split(',').map(|s| s.parse().unwrap()).collect_tuple().unwrap()).collect::<Vec<_>>();
-    let folds = folds.lines().map(|l| {
-        let (dir, val) = l.split('=').collect_tuple().unwrap();
-        (match &dir[(dir.len() - 1)..] {
-            "y" => Fold::Up,
-            "x" => Fold::Left,
-            _ => unreachable!()
-        })(val.parse().unwrap())
-    }).collect();
-    Paper {grid: Grid::parse(&coords), folds}
+fn parse(input: &str) -> Result<Paper, ParseError> {
+    let input = Input::new(13, input);
+    let (dot_lines, fold_lines) = input.split_once(input.text(), "\n\n")?;
+    let lines = dot_lines.lines().collect::<Vec<_>>();
+    let coords = lines.iter().map(|l| {
+        let (x, y) = input.split_once(l, ",")?;
+        Ok((input.parse(x)?, input.parse(y)?))
+    }).collect::<Result<Vec<_>, _>>()?;
+    if coords.is_empty() {
+        return Err(input.missing(dot_lines, "at least one dot"));
+    }
+    let folds = fold_lines.lines().map(|l| {
+        let (dir, val) = input.split_once(l, "=")?;
+        Ok((match dir {
+            "fold along y" => Fold::Up,
+            "fold along x" => Fold::Left,
+            _ => return Err(input.error(dir, "expected `fold along x` or `fold along y`"))
+        })(input.parse(val)?))
+    }).collect::<Result<Vec<_>, _>>()?;
+    if folds.is_empty() {
+        return Err(input.missing(fold_lines, "at least one fold"));
+    }
+    // Folding a dot more than twice as far as the fold line would take it past the top or left edge, the folds keep
+    // the dots in the order of their lines
+    let mut grid = Grid::parse(&coords);
+    for (fold, fold_line) in folds.iter().zip(fold_lines.lines()) {
+        let beyond = grid.inner.iter().position(|&(x, y)| match *fold {
+            Fold::Up(along) => y.saturating_sub(along) > along,
+            Fold::Left(along) => x.saturating_sub(along) > along,
+        });
+        if let Some(i) = beyond {
+            return Err(input.error(lines[i], format!("expected a dot at most twice as far as `{}`", fold_line)));
+        }
+        grid.fold(fold);
+    }
+    Ok(Paper {grid: Grid::parse(&coords), folds})
 }
 
 #[aoc(day13, part1)]
@@ -102,6 +142,13 @@ fn part2(input: &Paper) -> String {
     paper.grid.print()
 }
 
+pub(super) fn solutions() -> Vec<Box<dyn Solver>> {
+    vec![
+        Solution::boxed(13, 1, None, parse, part1),
+        Solution::boxed(13, 2, None, parse, part2),
+    ]
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -129,6 +176,20 @@ mod tests {
 
 fold along y=7
 fold along x=5"#;
-        assert_eq!(part1(&parse(input)), 17);
+        assert_eq!(part1(&parse(input).unwrap()), 17);
+    }
+
+    #[test]
+    fn malformed_input() {
+        let error = |input: &str| parse(input).map(|_| ()).map_err(|e| (e.line, e.column, e.reason));
+        let fail = |line, column, reason: &str| Err((line, column, reason.to_string()));
+        assert_eq!(error("\n\nfold along x=2"), fail(1, 1, "expected at least one dot"));
+        assert_eq!(error("5,1\n\n"), fail(3, 1, "expected at least one fold"));
+        let far = "expected a dot at most twice as far as `fold along x=2`";
+        assert_eq!(error("1,1\n5,1\n\nfold along x=2"), fail(2, 1, far));
+        // 9,0 is folded within reach of the other folds, but 0,3 isn't
+        let twice = "9,0\n0,3\n\nfold along x=5\nfold along y=1\nfold along x=0";
+        assert_eq!(error(twice), fail(2, 1, "expected a dot at most twice as far as `fold along y=1`"));
+        assert_eq!(part1(&parse("4,0\n0,0\n\nfold along x=2").unwrap()), 1);
     }
 }
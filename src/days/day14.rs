@@ -1,5 +1,7 @@
 use fxhash::FxHashMap;
 use itertools::Itertools;
+use crate::parse::{Input as RawInput, ParseError};
+use super::{Solution, Solver};
 
 struct Input {
     template: String,
@@ -7,13 +9,18 @@ struct Input {
 }
 
 #[aoc_generator(day14)]
-fn parse(input: &str) -> Input {
-    let (template, instructions) = input.split("\n\n").collect_tuple().unwrap();
+fn parse(input: &str) -> Result<Input, ParseError> {
+    let input = RawInput::new(14, input);
+    let (template, instructions) = input.split_once(input.text(), "\n\n")?;
+    if template.is_empty() {
+        return Err(input.missing(template, "a polymer template"));
+    }
     let instructions = instructions.lines().map(|s| {
-        let (from, between) = s.split(" -> ").collect_tuple().unwrap();
-        (from.chars().collect_tuple().unwrap(), between.chars().next().unwrap())
-    }).collect();
-    Input { template: template.to_string(), instructions }
+        let (from, between) = input.split_once(s, " -> ")?;
+        let from = from.chars().collect_tuple().ok_or_else(|| input.error(from, "expected a pair of elements"))?;
+        Ok((from, input.char(between)?))
+    }).collect::<Result<_, _>>()?;
+    Ok(Input { template: template.to_string(), instructions })
 }
 
 #[aoc(day14, part1)]
@@ -26,6 +33,22 @@ fn part2(input: &Input) -> usize {
     calc(input, 40)
 }
 
+/// Builds the whole polymer, the length doubles at each step so this only works for part 1
+#[aoc(day14, part1, naive)]
+fn part1_naive(input: &Input) -> usize {
+    let mut polymer: Vec<char> = input.template.chars().collect();
+    for _ in 0..10 {
+        let mut next = Vec::with_capacity(2 * polymer.len());
+        for (&a, &b) in polymer.iter().tuple_windows() {
+            next.push(a);
+            next.push(input.instructions[&(a, b)]);
+        }
+        next.extend(polymer.last());
+        polymer = next;
+    }
+    polymer.into_iter().counts().into_values().minmax().into_option().map(|(min, max)| max - min).unwrap()
+}
+
 fn calc(input: &Input, steps: usize) -> usize {
     let mut pairs: FxHashMap<(char, char), usize> = FxHashMap::default();
     let mut chars: FxHashMap<char, usize> = FxHashMap::default();
@@ -59,6 +82,14 @@ fn calc(input: &Input, steps: usize) -> usize {
     chars.into_values().minmax().into_option().map(|(min, max)| max - min).unwrap()
 }
 
+pub(super) fn solutions() -> Vec<Box<dyn Solver>> {
+    vec![
+        Solution::boxed(14, 1, None, parse, part1),
+        Solution::boxed(14, 2, None, parse, part2),
+        Solution::boxed(14, 1, Some("naive"), parse, part1_naive),
+    ]
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -83,7 +114,8 @@ BB -> N
 BC -> B
 CC -> N
 CN -> C"#;
-        assert_eq!(part1(&parse(input)), 1588);
+        assert_eq!(part1(&parse(input).unwrap()), 1588);
+        assert_eq!(part1_naive(&parse(input).unwrap()), 1588);
     }
 
     #[test]
@@ -106,6 +138,6 @@ BB -> N
 BC -> B
 CC -> N
 CN -> C"#;
-        assert_eq!(part2(&parse(input)), 2188189693529);
+        assert_eq!(part2(&parse(input).unwrap()), 2188189693529);
     }
 }
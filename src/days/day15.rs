@@ -1,83 +1,69 @@
-use fxhash::FxHashMap;
-use itertools::Itertools;
 use pathfinding::prelude::dijkstra;
+use crate::grid::{Grid, Neighbours};
+use crate::parse::{Input, ParseError};
+use super::{Solution, Solver};
 
-#[derive(Debug, Clone)]
-struct Grid {
-    nodes: FxHashMap<(i32, i32), Node>,
-    start: Node,
-    end: Node
+/// The risk level of each position of the cave
+pub struct Cavern {
+    risks: Grid<u8>,
 }
 
-#[derive(Copy, Clone, Eq, Ord, PartialOrd, PartialEq, Hash, Debug, Default)]
-struct Node {
-    pos: (i32, i32),
-    cost: i32
-}
+impl Cavern {
+    pub fn parse(input: &str) -> Result<Self, ParseError> {
+        parse(input).map(|risks| Cavern { risks })
+    }
 
-impl Grid {
-    pub fn parse(nodes: Vec<Node>) -> Grid {
-        let start = nodes[0];
-        let end = nodes[nodes.len() - 1];
-        let nodes = nodes.into_iter().map(|n| (n.pos, n)).collect();
-        Grid {
-            start, end, nodes
-        }
+    pub fn risks(&self) -> &Grid<u8> {
+        &self.risks
     }
 
-    pub fn successors(&self, node: &Node) -> [(Node, i32); 4] {
-        let def = (*node, node.cost);
-        let mut res = [def, def, def, def];
-        let (node_x, node_y) = node.pos;
-        for (i, node) in (-1..=1).cartesian_product(-1..=1).filter(|(x, y)| (*x == 0) ^ (*y == 0))
-            .filter_map(|(x, y)| self.nodes.get(&(node_x + x, node_y + y)))
-            .enumerate() {
-            res[i] = (*node, node.cost);
-        }
-        res
+    /// Positions of the safest path from the top left to the bottom right corner, both included
+    pub fn safest_path(&self) -> Vec<(usize, usize)> {
+        safest_path(&self.risks).0
     }
 }
 
 #[aoc_generator(day15)]
-fn parse(input: &str) -> Grid {
-    let nodes = input.lines().enumerate().flat_map(|(y, l)| l.chars().enumerate().map(move |(x, c)| Node {
-        pos: (x as i32, y as i32),
-        cost: c.to_digit(10).unwrap() as i32
-    })).collect();
-    Grid::parse(nodes)
+fn parse(input: &str) -> Result<Grid<u8>, ParseError> {
+    let input = Input::new(15, input);
+    Grid::parse(&input, input.text(), "0123456789", |c| c.to_digit(10).unwrap() as u8)
 }
 
 #[aoc(day15, part1)]
-fn part1(input: &Grid) -> i32 {
-    let (_, cost) = dijkstra(&input.start, |n| input.successors(n), |n| *n == input.end).unwrap();
-    cost
+fn part1(input: &Grid<u8>) -> i32 {
+    lowest_risk(input)
 }
 
 #[aoc(day15, part2)]
-fn part2(input: &Grid) -> i32 {
-    let mut extended = input.clone();
-    let (w, l) = (input.end.pos.0 + 1, input.end.pos.1 + 1);
-    extended.nodes.reserve(w as usize * 5 + l as usize * 5);
-    for y in 0..5 {
-        for x in 0..5 {
-            if x == y && x == 0 {
-                continue;
-            }
-            for ((old_x, old_y), node) in &input.nodes {
-                let mut node = *node;
-                let new_pos = (w * x + *old_x, l * y + *old_y);
-                node.pos = new_pos;
-                node.cost += x + y;
-                if node.cost > 9 {
-                    node.cost = (node.cost - 9).abs();
-                }
-                extended.nodes.insert(new_pos, node);
-            }
-        }
-    }
-    extended.end = extended.nodes[&(w * 5 - 1, l * 5 - 1)];
-    let (_, cost) = dijkstra(&extended.start, |n| extended.successors(n), |n| *n == extended.end).unwrap();
-    cost
+fn part2(input: &Grid<u8>) -> i32 {
+    let (w, h) = (input.width(), input.height());
+    let extended = Grid::from_fn(w * 5, h * 5, |(x, y)| {
+        let risk = input[(x % w, y % h)] as i32 + (x / w + y / h) as i32;
+        // Risk levels above 9 wrap back around to 1, a risk of 0 stays 0 in the first tile
+        ((risk - 1) % 9 + 1) as u8
+    });
+    lowest_risk(&extended)
+}
+
+/// Total risk of the safest path from the top left to the bottom right corner
+fn lowest_risk(grid: &Grid<u8>) -> i32 {
+    safest_path(grid).1
+}
+
+fn safest_path(grid: &Grid<u8>) -> (Vec<(usize, usize)>, i32) {
+    let end = (grid.width() - 1, grid.height() - 1);
+    dijkstra(
+        &(0, 0),
+        |&pos| grid.neighbours(pos, Neighbours::Four).map(|n| (n, grid[n] as i32)),
+        |&pos| pos == end,
+    ).unwrap()
+}
+
+pub(super) fn solutions() -> Vec<Box<dyn Solver>> {
+    vec![
+        Solution::boxed(15, 1, None, parse, part1),
+        Solution::boxed(15, 2, None, parse, part2),
+    ]
 }
 
 #[cfg(test)]
@@ -97,7 +83,7 @@ mod tests {
 1293138521
 2311944581
 "#;
-        assert_eq!(part1(&parse(input)), 40);
+        assert_eq!(part1(&parse(input).unwrap()), 40);
     }
 
     #[test]
@@ -113,6 +99,13 @@ mod tests {
 1293138521
 2311944581
 "#;
-        assert_eq!(part2(&parse(input)), 315);
+        assert_eq!(part2(&parse(input).unwrap()), 315);
+    }
+
+    #[test]
+    fn zero_risk() {
+        // The tiles of the full map have risks 0 to 8, and each step of a shortest path enters the next one
+        assert_eq!(part1(&parse("0").unwrap()), 0);
+        assert_eq!(part2(&parse("0").unwrap()), (1..=8).sum::<i32>());
     }
 }
@@ -1,4 +1,5 @@
-use itertools::Itertools;
+use crate::parse::{Input, ParseError};
+use super::{Solution, Solver};
 
 #[derive(Debug)]
 struct Packet {
@@ -56,83 +57,100 @@ impl Packet {
     }
 }
 
+/// Decodes the packet, checking its structure so the parts can't fail
 #[aoc_generator(day16)]
-fn parse(input: &str) -> String {
-    let mut formatted = String::with_capacity(input.len() * 4);
-    for c in input.chars() {
-        formatted += &format!("{:04b}", c.to_digit(16).unwrap());
-    }
-    formatted
+fn parse(input: &str) -> Result<Packet, ParseError> {
+    let input = Input::new(16, input);
+    let text = input.text();
+    let digits = input.digits(text, 16)?;
+    let bits: Vec<u8> = digits.into_iter().flat_map(|d| (0..4).rev().map(move |i| d >> i & 1)).collect();
+    let mut reader = Bits { bits: &bits, pos: 0 };
+    let packet = read_packet(&mut reader).and_then(|packet| {
+        // Only zeros can pad the transmission
+        match bits[reader.pos..].iter().position(|&bit| bit == 1) {
+            Some(i) => Err((reader.pos + i, "unexpected bits after the packet")),
+            None => Ok(packet),
+        }
+    });
+    packet.map_err(|(pos, reason)| {
+        // The hex digit holding the bit, or the end of the input. The digits are ASCII, so one byte each.
+        let digit = (pos / 4).min(text.len());
+        input.error(&text[digit..(digit + 1).min(text.len())], reason)
+    })
 }
 
 #[aoc(day16, part1)]
-fn part1(mut input: &str) -> usize {
-    let packet = read_packet(&mut input);
+fn part1(input: &Packet) -> usize {
     let mut count = 0;
-    count_ver(&packet, &mut count);
+    count_ver(input, &mut count);
     count
 }
 
 #[aoc(day16, part2)]
-fn part2(mut input: &str) -> usize {
-    let packet = read_packet(&mut input);
-    packet.value()
+fn part2(input: &Packet) -> usize {
+    input.value()
 }
 
-fn read_packet(input: &mut &str) -> Packet {
-    let ver = usize::from_str_radix(&input[0..3], 2).unwrap();
-    let type_id = usize::from_str_radix(&input[3..6], 2).unwrap();
-    *input = &input[6..];
-    match type_id {
-        4 => {
-            let mut found = false;
-            let num = input.chars()
-                .tuples()
-                .filter_map(|(a, b, c, d, e)| {
-                    if found {
-                        return None;
-                    }
-                    if a == '0' {
-                        found = true;
-                    }
-                    Some([b, c, d, e])
-                })
-                .fuse()
-                .flatten()
-                .join("");
-            *input = &input[(num.len() + (num.len() / 4) as usize)..];
-            let num = usize::from_str_radix(&num, 2).unwrap();
-            Packet {version: ver, packet_type: PacketType::Literal(num)}
-        },
-        id => {
-            let len_type_id = input[0..1].parse().unwrap();
-            let mut read = vec![];
-            match len_type_id {
-                0 => {
-                    // size
-                    let size = usize::from_str_radix(&input[1..16], 2).unwrap();
-                    let mut buf = &input[16..(16 + size)];
-                    *input = &input[(16 + size)..];
-                    while !buf.is_empty() {
-                        read.push(read_packet(&mut buf));
-                    }
-                    Packet {version: ver, packet_type: PacketType::Operator(id as u8, read)}
-                },
-                1 => {
-                    // length
-                    let len = usize::from_str_radix(&input[1..12], 2).unwrap();
-                    *input = &input[12..];
-                    for _ in 0..len {
-                        let packet = read_packet(input);
-                        read.push(packet);
-                    }
-                    Packet {version: ver, packet_type: PacketType::Operator(id as u8, read)}
-                },
-                _ => unreachable!()
-            }
+/// The bits of the transmission, read from the front
+struct Bits<'a> {
+    bits: &'a [u8],
+    pos: usize,
+}
+
+/// Position of the offending bit, and what is wrong with it
+type BitError = (usize, &'static str);
 
+impl Bits<'_> {
+    fn read(&mut self, len: usize, missing: &'static str) -> Result<usize, BitError> {
+        let bits = self.bits.get(self.pos..self.pos + len).ok_or((self.pos, missing))?;
+        self.pos += len;
+        Ok(bits.iter().fold(0, |value, &bit| value << 1 | bit as usize))
+    }
+}
+
+fn read_packet(bits: &mut Bits) -> Result<Packet, BitError> {
+    let start = bits.pos;
+    let version = bits.read(3, "expected a packet version")?;
+    let type_id = bits.read(3, "expected a packet type")?;
+    if type_id == 4 {
+        let mut value = 0usize;
+        loop {
+            let group = bits.pos;
+            let more = bits.read(1, "expected a literal group")?;
+            let digits = bits.read(4, "expected a literal group")?;
+            if value >> (usize::BITS - 4) != 0 {
+                return Err((group, "literal value too large"));
+            }
+            value = value << 4 | digits;
+            if more == 0 {
+                return Ok(Packet { version, packet_type: PacketType::Literal(value) });
+            }
         }
     }
+    let mut read = vec![];
+    if bits.read(1, "expected a length type")? == 0 {
+        let size = bits.read(15, "expected the length of the sub-packets")?;
+        let end = bits.pos + size;
+        if end > bits.bits.len() {
+            return Err((bits.bits.len(), "sub-packets shorter than their length"));
+        }
+        while bits.pos < end {
+            read.push(read_packet(bits)?);
+        }
+        if bits.pos > end {
+            return Err((end, "sub-packets longer than their length"));
+        }
+    } else {
+        let count = bits.read(11, "expected the number of sub-packets")?;
+        for _ in 0..count {
+            read.push(read_packet(bits)?);
+        }
+    }
+    match (type_id, read.len()) {
+        (0..=3, 0) => Err((start, "operator without sub-packets")),
+        (5..=7, len) if len != 2 => Err((start, "comparison without exactly two sub-packets")),
+        _ => Ok(Packet { version, packet_type: PacketType::Operator(type_id as u8, read) }),
+    }
 }
 
 fn count_ver(packet: &Packet, count: &mut usize) {
@@ -144,6 +162,13 @@ fn count_ver(packet: &Packet, count: &mut usize) {
     }
 }
 
+pub(super) fn solutions() -> Vec<Box<dyn Solver>> {
+    vec![
+        Solution::boxed(16, 1, None, parse, part1),
+        Solution::boxed(16, 2, None, parse, part2),
+    ]
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -151,12 +176,29 @@ mod tests {
     #[test]
     fn part1_example() {
         let input = r#"A0016C880162017C3686B18A3D4780"#;
-        assert_eq!(part1(&parse(input)), 31);
+        assert_eq!(part1(&parse(input).unwrap()), 31);
     }
 
     #[test]
     fn part2_example() {
         let input = r#"9C0141080250320F1802104A08"#;
-        assert_eq!(part2(&parse(input)), 1);
+        assert_eq!(part2(&parse(input).unwrap()), 1);
+    }
+
+    #[test]
+    fn malformed_input() {
+        let error = |input| {
+            let err = parse(input).err().unwrap();
+            (err.column, err.token, err.reason)
+        };
+        assert_eq!(error(""), (1, "".to_string(), "expected a packet version".to_string()));
+        // A literal missing the end of its last group
+        assert_eq!(error("D2FE2"), (5, "2".to_string(), "expected a literal group".to_string()));
+        assert_eq!(error("D2FE28F"), (7, "F".to_string(), "unexpected bits after the packet".to_string()));
+        assert_eq!(error("38006F452912").2, "sub-packets shorter than their length");
+        // A less-than with a single sub-packet
+        assert_eq!(error("16004408"), (1, "1".to_string(), "comparison without exactly two sub-packets".to_string()));
+        assert_eq!(error("D2FE2G").1, "G");
+        assert_eq!(part2(&parse("D2FE28").unwrap()), 2021);
     }
 }
@@ -1,14 +1,19 @@
 use std::cmp::Ordering;
 use std::ops::RangeInclusive;
 use regex::Regex;
+use crate::parse::{Input, ParseError};
+use super::{Solution, Solver};
 
 #[aoc_generator(day17)]
-fn parse(input: &str) -> (RangeInclusive<i32>, RangeInclusive<i32>) {
+fn parse(input: &str) -> Result<(RangeInclusive<i32>, RangeInclusive<i32>), ParseError> {
     let regex = Regex::new(r#"target area: x=(-?\d+)..(-?\d+), y=(-?\d+)..(-?\d+)"#).unwrap();
-    let caps = regex.captures(input).unwrap();
-    let (x1, x2) = (caps[1].parse::<i32>().unwrap(), caps[2].parse::<i32>().unwrap());
-    let (y1, y2) = (caps[3].parse::<i32>().unwrap(), caps[4].parse::<i32>().unwrap());
-    (x1.min(x2)..=x1.max(x2), y1.min(y2)..=y1.max(y2))
+    let input = Input::new(17, input);
+    let caps = regex.captures(input.text())
+        .ok_or_else(|| input.error(input.text(), "expected `target area: x=<x1>..<x2>, y=<y1>..<y2>`"))?;
+    let num = |i| input.parse::<i32>(caps.get(i).unwrap().as_str());
+    let (x1, x2) = (num(1)?, num(2)?);
+    let (y1, y2) = (num(3)?, num(4)?);
+    Ok((x1.min(x2)..=x1.max(x2), y1.min(y2)..=y1.max(y2)))
 }
 
 #[aoc(day17, part1)]
@@ -87,6 +92,13 @@ fn part2((target_x, target_y): &(RangeInclusive<i32>, RangeInclusive<i32>)) -> u
     count
 }
 
+pub(super) fn solutions() -> Vec<Box<dyn Solver>> {
+    vec![
+        Solution::boxed(17, 1, None, parse, part1),
+        Solution::boxed(17, 2, None, parse, part2),
+    ]
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -94,12 +106,12 @@ mod tests {
     #[test]
     fn part1_example() {
         let input = r#"target area: x=20..30, y=-10..-5"#;
-        assert_eq!(part1(&parse(input)), 45);
+        assert_eq!(part1(&parse(input).unwrap()), 45);
     }
 
     #[test]
     fn part2_example() {
         let input = r#"target area: x=20..30, y=-10..-5"#;
-        assert_eq!(part2(&parse(input)), 112);
+        assert_eq!(part2(&parse(input).unwrap()), 112);
     }
 }
@@ -3,6 +3,8 @@ use std::ops::{Deref, DerefMut};
 use std::rc::Rc;
 
 use itertools::Itertools;
+use crate::parse::{Input, ParseError};
+use super::{Solution, Solver};
 
 // This needs some serious refactoring
 
@@ -242,9 +244,67 @@ impl Pair {
     }
 }
 
-#[aoc_generator(day18)]
-fn parse(input: &str) -> Vec<String> {
-    input.lines().map(|s| s.to_string()).collect()
+#[aoc_generator(day18, part1)]
+fn parse(input: &str) -> Result<Vec<String>, ParseError> {
+    let input = Input::new(18, input);
+    let numbers: Vec<String> = input.lines().map(|s| {
+        // Pair::parse expects well-formed numbers, so check them beforehand
+        let mut rest = s;
+        check_pair(&input, &mut rest)?;
+        if !rest.is_empty() {
+            return Err(input.error(rest, "unexpected trailing characters"));
+        }
+        Ok(s.to_string())
+    }).collect::<Result<_, _>>()?;
+    if numbers.is_empty() {
+        return Err(input.missing(input.text(), "a snailfish number"));
+    }
+    Ok(numbers)
+}
+
+/// Also checks that there are two numbers, part 2 adds two different ones
+#[aoc_generator(day18, part2)]
+fn parse_pairs(input: &str) -> Result<Vec<String>, ParseError> {
+    let numbers = parse(input)?;
+    if numbers.len() < 2 {
+        return Err(Input::new(18, input).missing(input, "at least two snailfish numbers"));
+    }
+    Ok(numbers)
+}
+
+fn check_pair(input: &Input, rest: &mut &str) -> Result<(), ParseError> {
+    expect_char(input, rest, '[')?;
+    check_item(input, rest)?;
+    expect_char(input, rest, ',')?;
+    check_item(input, rest)?;
+    expect_char(input, rest, ']')
+}
+
+fn check_item(input: &Input, rest: &mut &str) -> Result<(), ParseError> {
+    if rest.starts_with('[') {
+        return check_pair(input, rest);
+    }
+    let len = rest.chars().take_while(|c| c.is_ascii_digit()).count();
+    if len == 0 {
+        return Err(input.error(first_char(rest), "expected a number or a pair"));
+    }
+    input.parse::<i32>(&rest[..len])?;
+    *rest = &rest[len..];
+    Ok(())
+}
+
+fn expect_char(input: &Input, rest: &mut &str, c: char) -> Result<(), ParseError> {
+    match rest.strip_prefix(c) {
+        Some(r) => {
+            *rest = r;
+            Ok(())
+        }
+        None => Err(input.error(first_char(rest), format!("expected `{}`", c))),
+    }
+}
+
+fn first_char(s: &str) -> &str {
+    &s[..s.chars().next().map_or(0, char::len_utf8)]
 }
 
 #[aoc(day18, part1)]
@@ -275,6 +335,13 @@ fn part2(input: &[String]) -> i32 {
     }).max().unwrap()
 }
 
+pub(super) fn solutions() -> Vec<Box<dyn Solver>> {
+    vec![
+        Solution::boxed(18, 1, None, parse, part1),
+        Solution::boxed(18, 2, None, parse_pairs, part2),
+    ]
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -291,7 +358,7 @@ mod tests {
 [[9,3],[[9,9],[6,[4,9]]]]
 [[2,[[7,7],7]],[[5,8],[[9,3],[0,2]]]]
 [[[[5,2],5],[8,[3,7]]],[[5,[7,5]],[4,4]]]"#;
-        assert_eq!(part1(&parse(input)), 4140);
+        assert_eq!(part1(&parse(input).unwrap()), 4140);
     }
 
     #[test]
@@ -306,6 +373,22 @@ mod tests {
 [[9,3],[[9,9],[6,[4,9]]]]
 [[2,[[7,7],7]],[[5,8],[[9,3],[0,2]]]]
 [[[[5,2],5],[8,[3,7]]],[[5,[7,5]],[4,4]]]"#;
-        assert_eq!(part2(&parse(input)), 3993);
+        assert_eq!(part2(&parse_pairs(input).unwrap()), 3993);
+    }
+
+    #[test]
+    fn malformed_input() {
+        let err = parse("").err().unwrap();
+        assert_eq!((err.line, err.reason.as_str()), (1, "expected a snailfish number"));
+        let err = parse_pairs("[1,2]").err().unwrap();
+        assert_eq!((err.line, err.column, err.reason.as_str()), (1, 6, "expected at least two snailfish numbers"));
+        // A single number is its own sum for part 1
+        assert_eq!(part1(&parse("[[1,2],[[3,4],5]]").unwrap()), 143);
+        let err = parse("[1,2]\n[1,2]]").err().unwrap();
+        assert_eq!((err.line, err.column, err.token.as_str()), (2, 6, "]"));
+        let err = parse("[1,2]\n[[1,x],2]").err().unwrap();
+        assert_eq!((err.column, err.reason.as_str()), (5, "expected a number or a pair"));
+        let err = parse("[1,2]\n[99999999999,2]").err().unwrap();
+        assert_eq!((err.column, err.token.as_str()), (2, "99999999999"));
     }
 }
@@ -1,6 +1,8 @@
 use std::ops::{Add, Sub};
 use fxhash::{FxBuildHasher, FxHashMap, FxHashSet};
 use itertools::Itertools;
+use crate::parse::{Input, ParseError};
+use super::{Solution, Solver};
 
 #[derive(Clone, Debug)]
 struct Scanner {
@@ -111,7 +113,7 @@ impl <'a> Sub for &'a Scanner {
                 *counts.entry(i).or_default() += 1
             });
             for (k, v) in counts {
-                if v >= 12 {
+                if v >= OVERLAP {
                     return Some((k, i));
                 }
             }
@@ -120,13 +122,30 @@ impl <'a> Sub for &'a Scanner {
     }
 }
 
+/// Fewest beacons two scanners have in common, so fewer can't be matched
+const OVERLAP: usize = 12;
+
 #[aoc_generator(day19)]
-fn parse(input: &str) -> Vec<Scanner> {
-    input.split("\n\n").into_iter()
-        .map(|b| b.lines().skip(1).map(|s| s.split(',')
-            .map(|s| s.parse().unwrap())
-            .collect_tuple::<(i32, i32, i32)>().unwrap().into())
-            .collect::<Vec<Pos>>().into())
+fn parse(input: &str) -> Result<Vec<Scanner>, ParseError> {
+    let input = Input::new(19, input);
+    input.text().split("\n\n").enumerate()
+        .map(|(i, b)| {
+            let mut lines = b.lines();
+            let header = input.expect(lines.next(), b, "a scanner header")?;
+            if header != format!("--- scanner {} ---", i) {
+                return Err(input.error(header, format!("expected `--- scanner {} ---`", i)));
+            }
+            let beacons = lines.map(|s| input.parse_list(s, ',')?
+                .into_iter()
+                .collect_tuple::<(i32, i32, i32)>()
+                .map(Pos::from)
+                .ok_or_else(|| input.error(s, "expected `x,y,z`")))
+                .collect::<Result<Vec<Pos>, _>>()?;
+            if beacons.len() < OVERLAP {
+                return Err(input.error(header, format!("expected at least {} beacons for the scanner", OVERLAP)));
+            }
+            Ok(Scanner::from(beacons))
+        })
         .collect()
 }
 
@@ -172,15 +191,18 @@ fn part2(input: &[Scanner]) -> i32 {
         *scanner.1 = rotate_to_zero(&distances, scanner.1.clone(), scanner.0, &mut progress).unwrap();
     }
 
-   scanners
+    // Scanner 0 is the origin
+    let locations = scanners
         .into_iter()
         .enumerate()
         .skip(1)
-        .map(|s| get_scanner_loc(&s.1, &input[s.0]).unwrap())
+        .map(|s| get_scanner_loc(&s.1, &input[s.0]).unwrap());
+    std::iter::once(ZERO)
+        .chain(locations)
         .permutations(2)
         .map(|p| p[0].distance(p[1]))
         .max()
-        .unwrap()
+        .unwrap_or(0)
 }
 
 fn get_scanner_loc(scanner: &Scanner, input_scanner: &Scanner) -> Option<Pos> {
@@ -216,18 +238,42 @@ fn rotate_to_zero(distances: &FxHashMap<usize, FxHashMap<usize, (Pos, i32)>>, mu
     }
 }
 
+pub(super) fn solutions() -> Vec<Box<dyn Solver>> {
+    vec![
+        Solution::boxed(19, 1, None, parse, part1),
+        Solution::boxed(19, 2, None, parse, part2),
+    ]
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn part1_example() {
-        assert_eq!(part1(&parse(INPUT)), 79);
+        assert_eq!(part1(&parse(INPUT).unwrap()), 79);
     }
 
     #[test]
     fn part2_example() {
-        assert_eq!(part2(&parse(INPUT)), 3621);
+        assert_eq!(part2(&parse(INPUT).unwrap()), 3621);
+    }
+
+    #[test]
+    fn malformed_input() {
+        let err = parse("x").err().unwrap();
+        assert_eq!((err.line, err.token.as_str(), err.reason.as_str()), (1, "x", "expected `--- scanner 0 ---`"));
+        let err = parse("").err().unwrap();
+        assert_eq!(err.reason, "expected a scanner header");
+        let first = INPUT.split("\n\n").next().unwrap();
+        let err = parse(&format!("{}\n\n--- scanner 2 ---\n1,2,3", first)).err().unwrap();
+        assert_eq!((err.line, err.reason.as_str()), (28, "expected `--- scanner 1 ---`"));
+        let err = parse(&format!("{}\n\n--- scanner 1 ---\n1,2,3", first)).err().unwrap();
+        assert_eq!((err.line, err.reason.as_str()), (28, "expected at least 12 beacons for the scanner"));
+        let err = parse("--- scanner 0 ---\n1,2").err().unwrap();
+        assert_eq!((err.line, err.token.as_str()), (2, "1,2"));
+        // A lone scanner has no distance to another
+        assert_eq!(part2(&parse(first).unwrap()), 0);
     }
 
     static INPUT: &str = r#"--- scanner 0 ---
@@ -1,71 +1,440 @@
-use std::borrow::Borrow;
+use std::fmt::{self, Display};
+use crate::parse::{Input, ParseError};
+use super::{Solution, Solver};
 
-#[derive(Default)]
-struct Submarine {
-    pos: i32,
-    /// Depth in part 1, aim in part 2
-    depth_aim: i32,
-    /// Depth for part 2, because the old field is used for aim
-    new_depth: i32,
+/// How commands move the submarine. Each model keeps its own state, so a fresh one is needed for every run.
+pub trait MovementModel {
+    /// Returns `None` if a value overflows
+    fn apply(&mut self, instruction: &Instruction) -> Option<()>;
+
+    fn position(&self) -> i64;
+
+    fn depth(&self) -> i64;
+
+    /// Other values worth tracing, like the aim
+    fn extra(&self) -> Vec<(&'static str, i64)> {
+        vec![]
+    }
 }
 
-enum Instruction {
-    Forward(i32),
-    Up(i32),
-    Down(i32),
+/// Part 1: up and down change the depth directly
+#[derive(Clone, Debug, Default)]
+pub struct Simple {
+    pos: i64,
+    depth: i64,
 }
 
-impl Instruction {
-    fn apply(&self, submarine: &mut Submarine, advanced: bool) {
-        match self {
+impl MovementModel for Simple {
+    fn apply(&mut self, instruction: &Instruction) -> Option<()> {
+        match *instruction {
+            Instruction::Forward(val) => self.pos = self.pos.checked_add(val)?,
+            Instruction::Up(val) => self.depth = self.depth.checked_sub(val)?,
+            Instruction::Down(val) => self.depth = self.depth.checked_add(val)?,
+        }
+        Some(())
+    }
+
+    fn position(&self) -> i64 {
+        self.pos
+    }
+
+    fn depth(&self) -> i64 {
+        self.depth
+    }
+}
+
+/// Part 2: up and down change the aim, moving forward dives along it
+#[derive(Clone, Debug, Default)]
+pub struct Aimed {
+    pos: i64,
+    depth: i64,
+    aim: i64,
+}
+
+impl MovementModel for Aimed {
+    fn apply(&mut self, instruction: &Instruction) -> Option<()> {
+        match *instruction {
             Instruction::Forward(val) => {
-                submarine.pos += *val;
-                if advanced {
-                    submarine.new_depth += submarine.depth_aim * val;
-                }
+                let depth = self.depth.checked_add(self.aim.checked_mul(val)?)?;
+                self.pos = self.pos.checked_add(val)?;
+                self.depth = depth;
             }
-            Instruction::Up(val) => submarine.depth_aim -= *val,
-            Instruction::Down(val) => submarine.depth_aim += *val,
+            Instruction::Up(val) => self.aim = self.aim.checked_sub(val)?,
+            Instruction::Down(val) => self.aim = self.aim.checked_add(val)?,
+        }
+        Some(())
+    }
+
+    fn position(&self) -> i64 {
+        self.pos
+    }
+
+    fn depth(&self) -> i64 {
+        self.depth
+    }
+
+    fn extra(&self) -> Vec<(&'static str, i64)> {
+        vec![("aim", self.aim)]
+    }
+}
+
+/// Part 1, with a current pushing the submarine forward after every command
+#[derive(Clone, Debug, Default)]
+pub struct Drift {
+    inner: Simple,
+    current: i64,
+}
+
+impl Drift {
+    pub fn new(current: i64) -> Self {
+        Drift { inner: Simple::default(), current }
+    }
+}
+
+impl MovementModel for Drift {
+    fn apply(&mut self, instruction: &Instruction) -> Option<()> {
+        self.inner.apply(instruction)?;
+        self.inner.pos = self.inner.pos.checked_add(self.current)?;
+        Some(())
+    }
+
+    fn position(&self) -> i64 {
+        self.inner.pos
+    }
+
+    fn depth(&self) -> i64 {
+        self.inner.depth
+    }
+}
+
+/// Part 2, with the depth kept between the surface and `max_depth`
+#[derive(Clone, Debug, Default)]
+pub struct Bounded {
+    inner: Aimed,
+    max_depth: i64,
+}
+
+impl Bounded {
+    pub fn new(max_depth: i64) -> Self {
+        Bounded { inner: Aimed::default(), max_depth }
+    }
+}
+
+impl MovementModel for Bounded {
+    fn apply(&mut self, instruction: &Instruction) -> Option<()> {
+        self.inner.apply(instruction)?;
+        self.inner.depth = self.inner.depth.clamp(0, self.max_depth);
+        Some(())
+    }
+
+    fn position(&self) -> i64 {
+        self.inner.pos
+    }
+
+    fn depth(&self) -> i64 {
+        self.inner.depth
+    }
+
+    fn extra(&self) -> Vec<(&'static str, i64)> {
+        self.inner.extra()
+    }
+}
+
+type ModelFactory = Box<dyn Fn() -> Box<dyn MovementModel> + Send + Sync>;
+
+/// Movement models by name, creating a fresh state for each run
+pub struct Models {
+    factories: Vec<(String, ModelFactory)>,
+}
+
+impl Models {
+    /// `simple` and `aim` for the puzzle's parts, plus `drift` (a current of 1) and `bounded` (no flying)
+    pub fn builtin() -> Self {
+        let mut models = Models { factories: vec![] };
+        models.register("simple", || Box::new(Simple::default()));
+        models.register("aim", || Box::new(Aimed::default()));
+        models.register("drift", || Box::new(Drift::new(1)));
+        models.register("bounded", || Box::new(Bounded::new(i64::MAX)));
+        models
+    }
+
+    /// Adds a model, replacing any other one with the same name
+    pub fn register(&mut self, name: &str, factory: impl Fn() -> Box<dyn MovementModel> + Send + Sync + 'static) {
+        self.factories.retain(|(n, _)| n != name);
+        self.factories.push((name.to_string(), Box::new(factory)));
+    }
+
+    pub fn create(&self, name: &str) -> Option<Box<dyn MovementModel>> {
+        self.factories.iter().find(|(n, _)| n == name).map(|(_, factory)| factory())
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.factories.iter().map(|(n, _)| n.as_str())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Instruction {
+    Forward(i64),
+    Up(i64),
+    Down(i64),
+}
+
+impl Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Instruction::Forward(val) => write!(f, "forward {}", val),
+            Instruction::Up(val) => write!(f, "up {}", val),
+            Instruction::Down(val) => write!(f, "down {}", val),
         }
     }
 }
 
-impl<I: Iterator<Item = impl Borrow<str>>> From<I> for Instruction {
-    fn from(mut iter: I) -> Self {
-        let op = iter.next().unwrap();
-        let op = op.borrow();
-        let val = iter.next().unwrap();
-        let val = val.borrow().parse::<i32>().unwrap();
-        (match op {
+impl Instruction {
+    fn parse(input: &Input, line: &str) -> Result<Self, ParseError> {
+        let mut split = line.split_whitespace();
+        let op = input.expect(split.next(), line, "an instruction")?;
+        let val = input.expect(split.next(), line, "a value")?;
+        let val = input.parse(val)?;
+        if let Some(extra) = split.next() {
+            // Up to the end of the line, which comments were trimmed from
+            let rest = &line[extra.as_ptr() as usize - line.as_ptr() as usize..];
+            return Err(input.error(rest, "unexpected tokens after the value"));
+        }
+        Ok((match op {
             "forward" => Instruction::Forward,
             "up" => Instruction::Up,
             "down" => Instruction::Down,
-            s => panic!("unknown instruction {}", s),
-        })(val)
+            s => return Err(input.error(s, "unknown instruction")),
+        })(val))
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Statement {
+    /// An instruction, with its line number
+    Instruction(Instruction, usize),
+    /// A block repeated some times, with the line number of the `repeat`
+    Repeat(u32, Vec<Statement>, usize),
+    /// Index of the macro in [`Script::macros`], with the line number of the call
+    Call(usize, usize),
+}
+
+impl Statement {
+    fn line(&self) -> usize {
+        match *self {
+            Statement::Instruction(_, line) | Statement::Repeat(_, _, line) | Statement::Call(_, line) => line,
+        }
     }
 }
 
+#[derive(Clone, Debug)]
+struct Macro {
+    name: String,
+    body: Vec<Statement>,
+}
+
+/// Submarine commands, one per line, plus:
+///
+/// ```text
+/// # comments, at the start or the end of a line
+/// repeat 3 {
+///     forward 2
+/// }
+/// macro dive {
+///     down 5
+///     forward 1
+/// }
+/// dive
+/// ```
+///
+/// Macros are defined outside of blocks, before being used, so they can't recurse.
+#[derive(Clone, Debug)]
+pub struct Script {
+    statements: Vec<Statement>,
+    macros: Vec<Macro>,
+}
+
+impl Script {
+    /// Only checks the syntax, [`Script::run`] reports overflows
+    pub fn parse(input: &str) -> Result<Self, ParseError> {
+        parse_script(input)
+    }
+
+    /// Runs every instruction with `model`, calling `trace` after each one with its line number. Stops at the first
+    /// instruction overflowing the model.
+    pub fn run<M: MovementModel + ?Sized>(
+        &self,
+        model: &mut M,
+        trace: &mut impl FnMut(usize, &Instruction, &M),
+    ) -> Result<(), RunError> {
+        self.run_block(&self.statements, model, trace)
+    }
+
+    fn run_block<M: MovementModel + ?Sized>(
+        &self,
+        block: &[Statement],
+        model: &mut M,
+        trace: &mut impl FnMut(usize, &Instruction, &M),
+    ) -> Result<(), RunError> {
+        for statement in block {
+            match statement {
+                Statement::Instruction(inst, line) => {
+                    model.apply(inst).ok_or(RunError::Overflow { line: *line })?;
+                    trace(*line, inst, model);
+                }
+                Statement::Repeat(count, body, _) => {
+                    for _ in 0..*count {
+                        self.run_block(body, model, trace)?;
+                    }
+                }
+                Statement::Call(index, _) => self.run_block(&self.macros[*index].body, model, trace)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// How many instructions running `block` executes, saturating at `u64::MAX`
+    fn steps(&self, block: &[Statement]) -> u64 {
+        block.iter().fold(0u64, |total, statement| total.saturating_add(match statement {
+            Statement::Instruction(..) => 1,
+            Statement::Repeat(count, body, _) => (*count as u64).saturating_mul(self.steps(body)),
+            Statement::Call(index, _) => self.steps(&self.macros[*index].body),
+        }))
+    }
+
+    /// Runs the script with a model from `models`, returning the final position times depth
+    pub fn product(&self, models: &Models, model: &str) -> Result<i128, RunError> {
+        let mut model = models.create(model).ok_or_else(|| RunError::UnknownModel(model.to_string()))?;
+        self.run(model.as_mut(), &mut |_, _, _| {})?;
+        Ok(model.position() as i128 * model.depth() as i128)
+    }
+}
+
+/// Why a script could not be run
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RunError {
+    UnknownModel(String),
+    /// A value outgrew an `i64` running the instruction on `line`
+    Overflow { line: usize },
+}
+
+impl Display for RunError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RunError::UnknownModel(name) => write!(f, "unknown model `{}`", name),
+            RunError::Overflow { line } => write!(f, "the submarine goes out of range at line {}", line),
+        }
+    }
+}
+
+impl std::error::Error for RunError {}
+
+/// Parses statements up to the `}` closing the block opened by `opening`, or to the end of the input
+fn parse_block<'a>(
+    input: &Input<'a>,
+    lines: &mut impl Iterator<Item = (usize, &'a str)>,
+    macros: &mut Vec<Macro>,
+    opening: Option<&'a str>,
+) -> Result<Vec<Statement>, ParseError> {
+    let mut block = vec![];
+    while let Some((index, line)) = lines.next() {
+        let line = line.split('#').next().unwrap().trim();
+        let mut words = line.split_whitespace();
+        let Some(word) = words.next() else {
+            continue;
+        };
+        let open_block = |words: &mut std::str::SplitWhitespace<'a>, what| {
+            let brace = input.expect(words.next(), line, "`{`")?;
+            if brace != "{" {
+                return Err(input.error(brace, format!("expected `{{` to open the {}", what)));
+            }
+            Ok(())
+        };
+        match word {
+            "}" if opening.is_some() => return Ok(block),
+            "}" => return Err(input.error(word, "no block to close")),
+            "repeat" => {
+                let count = input.parse(input.expect(words.next(), line, "a repeat count")?)?;
+                open_block(&mut words, "repeated block")?;
+                block.push(Statement::Repeat(count, parse_block(input, lines, macros, Some(word))?, index + 1));
+            }
+            "macro" => {
+                if opening.is_some() {
+                    return Err(input.error(word, "macros can only be defined outside of blocks"));
+                }
+                let name = input.expect(words.next(), line, "a macro name")?;
+                if matches!(name, "forward" | "up" | "down" | "repeat" | "macro" | "{" | "}")
+                    || macros.iter().any(|m| m.name == name)
+                {
+                    return Err(input.error(name, "name already taken"));
+                }
+                open_block(&mut words, "macro")?;
+                let body = parse_block(input, lines, macros, Some(word))?;
+                macros.push(Macro { name: name.to_string(), body });
+            }
+            "forward" | "up" | "down" => {
+                block.push(Statement::Instruction(Instruction::parse(input, line)?, index + 1));
+            }
+            name => match macros.iter().position(|m| m.name == name) {
+                Some(macro_index) => block.push(Statement::Call(macro_index, index + 1)),
+                None => return Err(input.error(name, "unknown instruction")),
+            },
+        }
+    }
+    match opening {
+        Some(token) => Err(input.error(token, "block is never closed")),
+        None => Ok(block),
+    }
+}
+
+/// Most instructions the parts run, repeats can make short scripts run for ages
+const MAX_STEPS: u64 = 1 << 24;
+
+/// Also runs the script with the models of both parts, which have no answer if it overflows them
 #[aoc_generator(day2)]
-fn parse(input: &str) -> Vec<Instruction> {
-    input.lines().map(|l| l.split(' ').into()).collect()
+fn parse(input: &str) -> Result<Script, ParseError> {
+    let script = parse_script(input)?;
+    let input = Input::new(2, input);
+    let mut steps = 0u64;
+    for statement in &script.statements {
+        steps = steps.saturating_add(script.steps(std::slice::from_ref(statement)));
+        if steps > MAX_STEPS {
+            let text = input.lines().nth(statement.line() - 1).expect("statements are on a line of the input").trim();
+            return Err(input.error(text, format!("the script runs more than {} instructions", MAX_STEPS)));
+        }
+    }
+    for model in ["simple", "aim"] {
+        if let Err(RunError::Overflow { line }) = script.product(&Models::builtin(), model) {
+            let text = input.lines().nth(line - 1).expect("instructions are on a line of the input").trim();
+            return Err(input.error(text, format!("the submarine goes out of range with the `{}` model", model)));
+        }
+    }
+    Ok(script)
+}
+
+fn parse_script(input: &str) -> Result<Script, ParseError> {
+    let input = Input::new(2, input);
+    let mut macros = vec![];
+    let statements = parse_block(&input, &mut input.lines().enumerate(), &mut macros, None)?;
+    Ok(Script { statements, macros })
 }
 
 #[aoc(day2, part1)]
-fn part1(input: &[Instruction]) -> i32 {
-    let mut submarine = Submarine::default();
-    for inst in input {
-        inst.apply(&mut submarine, false);
-    }
-    submarine.pos * submarine.depth_aim
+fn part1(input: &Script) -> i128 {
+    input.product(&Models::builtin(), "simple").expect("checked by the generator")
 }
 
 #[aoc(day2, part2)]
-fn part2(input: &[Instruction]) -> i32 {
-    let mut submarine = Submarine::default();
-    for inst in input {
-        inst.apply(&mut submarine, true);
-    }
-    submarine.pos * submarine.new_depth
+fn part2(input: &Script) -> i128 {
+    input.product(&Models::builtin(), "aim").expect("checked by the generator")
+}
+
+pub(super) fn solutions() -> Vec<Box<dyn Solver>> {
+    vec![
+        Solution::boxed(2, 1, None, parse, part1),
+        Solution::boxed(2, 2, None, parse, part2),
+    ]
 }
 
 #[cfg(test)]
@@ -80,7 +449,7 @@ forward 8
 up 3
 down 8
 forward 2"#;
-        assert_eq!(part1(&parse(input)), 150);
+        assert_eq!(part1(&parse(input).unwrap()), 150);
     }
 
     #[test]
@@ -91,6 +460,116 @@ forward 8
 up 3
 down 8
 forward 2"#;
-        assert_eq!(part2(&parse(input)), 900);
+        assert_eq!(part2(&parse(input).unwrap()), 900);
+    }
+
+    #[test]
+    fn unknown_instruction() {
+        let err = parse("forward 5\nsideways 3").err().unwrap();
+        assert_eq!((err.line, err.column, err.token.as_str()), (2, 1, "sideways"));
+        let err = parse("forward 5\ndown").err().unwrap();
+        assert_eq!((err.line, err.column, err.reason.as_str()), (2, 5, "expected a value"));
+        let err = parse("forward 5 6 7 # comment").err().unwrap();
+        assert_eq!((err.line, err.column, err.token.as_str()), (1, 11, "6 7"));
+    }
+
+    #[test]
+    fn scripts() {
+        let script = parse(
+            r#"# The example, going up and down once more
+macro dive {
+    down 5
+    forward 8 # inline comment
+}
+
+forward 5
+dive
+repeat 2 {
+    up 3
+    down 8
+    repeat 0 {
+        forward 100
+    }
+}
+forward 2"#,
+        )
+        .unwrap();
+        let mut trace = vec![];
+        let mut submarine = Aimed::default();
+        script.run(&mut submarine, &mut |line, inst, sub| {
+            trace.push(format!("{}: {} -> {} {} {}", line, inst, sub.pos, sub.depth, sub.aim));
+        })
+        .unwrap();
+        assert_eq!(trace[..3], ["7: forward 5 -> 5 0 0", "3: down 5 -> 5 0 5", "4: forward 8 -> 13 40 5"]);
+        assert_eq!(trace.len(), 8);
+        assert_eq!(part1(&script), 15 * 15);
+        assert_eq!(part2(&script), 15 * 70);
+    }
+
+    #[test]
+    fn movement_models() {
+        let script = parse("forward 5\ndown 5\nforward 8\nup 3\ndown 8\nforward 2").unwrap();
+        let mut models = Models::builtin();
+        // 3 forward for 6 commands
+        assert_eq!(script.product(&models, "drift"), Ok(21 * 10));
+        assert_eq!(script.product(&models, "bounded"), Ok(900));
+        assert_eq!(script.product(&models, "missing"), Err(RunError::UnknownModel("missing".to_string())));
+
+        models.register("bounded", || Box::new(Bounded::new(30)));
+        models.register("reverse", || Box::new(Drift::new(-1)));
+        assert_eq!(script.product(&models, "bounded"), Ok(15 * 30));
+        assert_eq!(script.product(&models, "reverse"), Ok(9 * 10));
+        assert_eq!(models.names().collect::<Vec<_>>(), ["simple", "aim", "drift", "bounded", "reverse"]);
+
+        let mut bounded = Bounded::new(100);
+        parse("up 2\nforward 10\ndown 3").unwrap().run(&mut bounded, &mut |_, _, _| {}).unwrap();
+        assert_eq!((bounded.depth(), bounded.extra()), (0, vec![("aim", 1)]));
+    }
+
+    #[test]
+    fn overflow() {
+        // Beyond an i32, but not an i64
+        let script = parse("repeat 1000000 {\n    forward 5000\n}\ndown 5000000").unwrap();
+        assert_eq!((part1(&script), part2(&script)), (25_000_000_000_000_000, 0));
+
+        let script = Script::parse("down 9000000000000000000\nrepeat 2 {\n    forward 1 # dive\n}").unwrap();
+        assert_eq!(script.product(&Models::builtin(), "simple"), Ok(2 * 9_000_000_000_000_000_000));
+        assert_eq!(script.product(&Models::builtin(), "aim"), Err(RunError::Overflow { line: 3 }));
+        let err = parse("down 9000000000000000000\nrepeat 2 {\n    forward 1 # dive\n}").err().unwrap();
+        assert_eq!((err.line, err.column, err.token.as_str()), (3, 5, "forward 1 # dive"));
+        assert_eq!(err.reason, "the submarine goes out of range with the `aim` model");
+
+        let mut drift = Drift::new(i64::MAX);
+        let err = Script::parse("forward 0\nforward 0").unwrap().run(&mut drift, &mut |_, _, _| {});
+        assert_eq!(err, Err(RunError::Overflow { line: 2 }));
+        assert!(parse("forward 9223372036854775808").is_err());
+    }
+
+    #[test]
+    fn long_scripts() {
+        let err = parse("up 1\nrepeat 4294967295 {\nrepeat 4294967295 {\nforward 1\n}\n}").err().unwrap();
+        assert_eq!((err.line, err.token.as_str()), (2, "repeat 4294967295 {"));
+        assert_eq!(err.reason, "the script runs more than 16777216 instructions");
+        let err = parse("macro m {\n  repeat 8388608 {\n    up 1\n  }\n}\nm\nforward 1\nm").err().unwrap();
+        assert_eq!((err.line, err.column, err.token.as_str()), (8, 1, "m"));
+        let script = Script::parse("repeat 8388608 {\nup 1\ndown 1\n}").unwrap();
+        assert_eq!(script.steps(&script.statements), MAX_STEPS);
+        // A syntax check doesn't count the instructions
+        assert!(Script::parse("repeat 4294967295 {\nrepeat 4294967295 {\nforward 1\n}\n}").is_ok());
+    }
+
+    #[test]
+    fn script_errors() {
+        let error = |script| {
+            let err = parse(script).err().unwrap();
+            (err.line, err.token, err.reason)
+        };
+        assert_eq!(error("repeat 2 {\nup 1"), (1, "repeat".to_string(), "block is never closed".to_string()));
+        assert_eq!(error("up 1\n}"), (2, "}".to_string(), "no block to close".to_string()));
+        assert_eq!(error("dive\nmacro dive {\n}"), (1, "dive".to_string(), "unknown instruction".to_string()));
+        assert_eq!(error("repeat 1 {\nmacro m {\n}\n}").2, "macros can only be defined outside of blocks");
+        assert_eq!(error("macro up {\n}").1, "up");
+        assert_eq!(error("repeat 2 [").2, "expected `{` to open the repeated block");
+        assert_eq!(error("repeat -1 {\n}").1, "-1");
     }
 }
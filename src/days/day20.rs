@@ -1,86 +1,107 @@
-use fxhash::FxHashSet;
 use itertools::Itertools;
 use rayon::prelude::*;
+use crate::grid::Grid;
+use crate::parse::{Input, ParseError};
+use crate::sim::Simulation;
+use super::{Solution, Solver};
 
 #[derive(Clone, Debug)]
-struct Image {
+pub struct Image {
     model: Vec<bool>,
-    input: FxHashSet<(isize, isize)>,
+    pixels: Grid<bool>,
+    /// Color of every pixel outside of `pixels`
     infinite_color: bool,
-
-    min_x: isize,
-    min_y: isize,
-    max_x: isize,
-    max_y: isize
+    generation: usize,
 }
 
 impl Image {
-    fn get_color(&self, pos: (isize, isize)) -> bool {
-        if pos.0 < self.min_x || pos.1 < self.min_y || pos.0 > self.max_x || pos.1 > self.max_y {
-            return self.infinite_color;
-        }
-        self.input.contains(&pos)
+    pub fn parse(input: &str) -> Result<Self, ParseError> {
+        parse(input)
     }
 
     #[inline(always)]
     fn flip(&mut self) {
-        let permutations =  ((self.min_x - 2)..(self.max_x + 2)).cartesian_product((self.min_y - 2)..(self.max_y + 2)).collect_vec();
-        self.input = permutations
-            .par_iter()
-            .filter_map(|(x, y)| {
+        // The image grows by one pixel on each side, pixels further away only see the infinite color
+        let (width, height) = (self.pixels.width() + 2, self.pixels.height() + 2);
+        let cells = (0..width * height)
+            .into_par_iter()
+            .map(|i| {
+                let (x, y) = ((i % width) as isize - 1, (i / width) as isize - 1);
                 let mut bits = 0usize;
-                (-1..=1).cartesian_product(-1..=1).enumerate().map(|(i, (dy, dx))| (i, self.get_color((x + dx, y + dy))))
-                    .filter(|(_, b)| *b).for_each(|(bit, _)| {
-                    bits |= 1 << (8 - bit);
-                });
-                if self.model[bits] {
-                    Some((*x, *y))
-                } else {
-                    None
+                for (dy, dx) in (-1..=1).cartesian_product(-1..=1) {
+                    bits = bits << 1 | *self.pixels.get_or((x + dx, y + dy), &self.infinite_color) as usize;
                 }
+                self.model[bits]
             }).collect();
+        self.pixels = Grid::from_vec(width, cells);
 
         self.infinite_color = self.model[if self.infinite_color { self.model.len() - 1 } else { 0 }];
+    }
+
+    pub fn lit(&self) -> usize {
+        self.pixels.cells().iter().filter(|&&p| p).count()
+    }
+
+    pub fn infinite_color(&self) -> bool {
+        self.infinite_color
+    }
+}
 
-        let (min_x, max_x) = self.input.iter().map(|&(x, _)| x).minmax().into_option().unwrap();
-        let (min_y, max_y) = self.input.iter().map(|&(_, y)| y).minmax().into_option().unwrap();
-        self.min_x = min_x;
-        self.max_x = max_x;
-        self.min_y = min_y;
-        self.max_y = max_y;
+impl Simulation for Image {
+    type Step = ();
+    /// The pixels that can differ from the infinite color, growing by one on each side at each step
+    type Snapshot = Grid<bool>;
+
+    fn step(&mut self) {
+        self.generation += 1;
+        self.flip();
+    }
+
+    fn generation(&self) -> usize {
+        self.generation
+    }
+
+    fn snapshot(&self) -> Grid<bool> {
+        self.pixels.clone()
     }
 }
 
 #[aoc_generator(day20)]
-fn parse(input: &str) -> Image {
-    let (model, input) = input.split("\n\n").collect_tuple().unwrap();
-    let model = model.chars().map(|c| c == '#').collect();
-    let line_len = input.lines().next().unwrap().len();
-    let max_y = input.lines().count() as isize - 1;
-    let input = input.lines().flat_map(|s| s.chars().map(|c| c == '#')).enumerate().filter(|(_, b)| *b).map(|(i, _)| {
-        let (x, y) = (i % line_len, i / line_len);
-        (x as isize, y as isize)
-    }).collect();
-    Image {
-        model, input, infinite_color: false, min_x: 0, min_y: 0, max_x: line_len as isize - 1, max_y
+fn parse(input: &str) -> Result<Image, ParseError> {
+    let raw = Input::new(20, input);
+    let (model, input) = raw.split_once(raw.text(), "\n\n")?;
+    if model.len() != 512 {
+        return Err(raw.error(model, "expected a 512 character enhancement algorithm"));
     }
+    let model = raw.chars(model, "#.")?.into_iter().map(|c| c == '#').collect();
+    let pixels = Grid::parse(&raw, input, "#.", |c| c == '#')?;
+    Ok(Image {
+        model, pixels, infinite_color: false, generation: 0
+    })
 }
 
 #[aoc(day20, part1)]
 fn part1(input: &Image) -> usize {
     let mut input = input.clone();
-    input.flip();
-    input.flip();
-    input.input.len()
+    input.step();
+    input.step();
+    input.lit()
 }
 
 #[aoc(day20, part2)]
 fn part2(input: &Image) -> usize {
     let mut input = input.clone();
     for _ in 0..50 {
-        input.flip();
+        input.step();
     }
-    input.input.len()
+    input.lit()
+}
+
+pub(super) fn solutions() -> Vec<Box<dyn Solver>> {
+    vec![
+        Solution::boxed(20, 1, None, parse, part1),
+        Solution::boxed(20, 2, None, parse, part2),
+    ]
 }
 
 #[cfg(test)]
@@ -97,11 +118,11 @@ mod tests {
 
     #[test]
     fn part1_example() {
-        assert_eq!(part1(&parse(INPUT)), 35);
+        assert_eq!(part1(&parse(INPUT).unwrap()), 35);
     }
 
     #[test]
     fn part2_example() {
-        assert_eq!(part2(&parse(INPUT)), 3351);
+        assert_eq!(part2(&parse(INPUT).unwrap()), 3351);
     }
 }
@@ -1,5 +1,7 @@
 use fxhash::{FxBuildHasher, FxHashMap};
 use itertools::Itertools;
+use crate::parse::{Input, ParseError};
+use super::{Solution, Solver};
 
 #[derive(Eq, PartialEq, Hash, Clone, Copy)]
 struct GameState {
@@ -9,8 +11,19 @@ struct GameState {
 }
 
 #[aoc_generator(day21)]
-fn parse(input: &str) -> Vec<usize> {
-    input.lines().map(|s| s.split(": ").last().unwrap().parse().unwrap()).collect()
+fn parse(input: &str) -> Result<Vec<usize>, ParseError> {
+    let input = Input::new(21, input);
+    let positions = input.lines().map(|s| {
+        let (_, pos) = input.split_once(s, ": ")?;
+        match input.parse(pos)? {
+            pos @ 1..=10 => Ok(pos),
+            _ => Err(input.error(pos, "expected a position between 1 and 10")),
+        }
+    }).collect::<Result<Vec<_>, _>>()?;
+    if positions.len() != 2 {
+        return Err(input.error(input.text(), "expected 2 players"));
+    }
+    Ok(positions)
 }
 
 #[aoc(day21, part1)]
@@ -95,6 +108,13 @@ fn run_game(scores: [usize; 2], positions: [usize; 2], cache: &mut FxHashMap<Gam
     winners
 }
 
+pub(super) fn solutions() -> Vec<Box<dyn Solver>> {
+    vec![
+        Solution::boxed(21, 1, None, parse, part1),
+        Solution::boxed(21, 2, None, parse, part2),
+    ]
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -103,13 +123,13 @@ mod tests {
     fn part1_example() {
         let input = r#"Player 1 starting position: 4
 Player 2 starting position: 8"#;
-        assert_eq!(part1(&parse(input)), 739785);
+        assert_eq!(part1(&parse(input).unwrap()), 739785);
     }
 
     #[test]
     fn part2_example() {
         let input = r#"Player 1 starting position: 4
 Player 2 starting position: 8"#;
-        assert_eq!(part2(&parse(input)), 444356092776315);
+        assert_eq!(part2(&parse(input).unwrap()), 444356092776315);
     }
 }
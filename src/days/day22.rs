@@ -1,6 +1,7 @@
 use std::ops::RangeInclusive;
-use itertools::Itertools;
 use regex::Regex;
+use crate::parse::{Input, ParseError};
+use super::{Solution, Solver};
 
 struct Instruction {
     on: bool,
@@ -82,22 +83,25 @@ impl Range3D {
 }
 
 #[aoc_generator(day22)]
-fn parse(input: &str) -> Vec<Instruction> {
+fn parse(input: &str) -> Result<Vec<Instruction>, ParseError> {
     let regex = Regex::new(r#"(on|off) x=(-?\d+)\.\.(-?\d+),y=(-?\d+)\.\.(-?\d+),z=(-?\d+)\.\.(-?\d+)"#).unwrap();
+    let input = Input::new(22, input);
     input.lines().map(|l| {
-        let caps = regex.captures(l).unwrap();
+        let caps = regex.captures(l)
+            .ok_or_else(|| input.error(l, "expected `on|off x=<x1>..<x2>,y=<y1>..<y2>,z=<z1>..<z2>`"))?;
         let on = caps[1] == *"on";
-        let (x1, x2) = [&caps[2], &caps[3]].iter().map(|&s| s.parse::<i32>().unwrap()).collect_tuple().unwrap();
-        let (y1, y2) = [&caps[4], &caps[5]].iter().map(|&s| s.parse::<i32>().unwrap()).collect_tuple().unwrap();
-        let (z1, z2) = [&caps[6], &caps[7]].iter().map(|&s| s.parse::<i32>().unwrap()).collect_tuple().unwrap();
-        Instruction {
+        let num = |i| input.parse::<i32>(caps.get(i).unwrap().as_str());
+        let (x1, x2) = (num(2)?, num(3)?);
+        let (y1, y2) = (num(4)?, num(5)?);
+        let (z1, z2) = (num(6)?, num(7)?);
+        Ok(Instruction {
             on,
             range: Range3D {
                 x: x1.min(x2)..=x1.max(x2),
                 y: y1.min(y2)..=y1.max(y2),
                 z: z1.min(z2)..=z1.max(z2),
             },
-        }
+        })
     }).collect()
 }
 
@@ -143,6 +147,13 @@ fn part2(input: &[Instruction]) -> isize {
     ranges.into_iter().map(|r| r.len()).sum()
 }
 
+pub(super) fn solutions() -> Vec<Box<dyn Solver>> {
+    vec![
+        Solution::boxed(22, 1, None, parse, part1),
+        Solution::boxed(22, 2, None, parse, part2),
+    ]
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -153,6 +164,6 @@ mod tests {
 on x=11..13,y=11..13,z=11..13
 off x=9..11,y=9..11,z=9..11
 on x=10..10,y=10..10,z=10..10"#;
-        assert_eq!(part1(&parse(input)), 39);
+        assert_eq!(part1(&parse(input).unwrap()), 39);
     }
 }
@@ -1,5 +1,7 @@
 use itertools::Itertools;
 use pathfinding::prelude::dijkstra;
+use crate::parse::{Input, ParseError};
+use super::{Solution, Solver};
 
 #[allow(clippy::ptr_arg)]
 fn calc_moves(grid: &Vec<Vec<u8>>) -> Vec<(Vec<Vec<u8>>, usize)> {
@@ -75,9 +77,49 @@ fn find_path(maze: &Vec<Vec<u8>>) -> usize {
              }).unwrap().1
 }
 
+/// Lines of the burrow at the start, `X` standing for an amphipod. The rooms can be any number of rows deep.
+const TOP: [&str; 3] = ["#############", "#...........#", "###X#X#X#X###"];
+const ROOM: &str = "  #X#X#X#X#";
+const BOTTOM: &str = "  #########";
+
 #[aoc_generator(day23)]
-fn parse(input: &str) -> Vec<Vec<u8>> {
-    input.lines().map(|l| l.bytes().collect()).collect()
+fn parse(input: &str) -> Result<Vec<Vec<u8>>, ParseError> {
+    let input = Input::new(23, input);
+    let lines: Vec<_> = input.lines().collect();
+    let complete = lines.len() > TOP.len();
+    // As many of each type as there are rows, once the bottom of the burrow is there
+    let depth = if complete { lines.len() - TOP.len() } else { usize::MAX };
+    let mut counts = [0; 4];
+    for (i, line) in lines.iter().map(|l| l.trim_end()).enumerate() {
+        let template = match i {
+            i if i < TOP.len() => TOP[i],
+            i if i == lines.len() - 1 => BOTTOM,
+            _ => ROOM,
+        };
+        for (j, c) in line.char_indices() {
+            let token = &line[j..j + c.len_utf8()];
+            match template.as_bytes().get(j) {
+                Some(b'X') if "ABCD".contains(c) => {
+                    let count = &mut counts[(c as u8 - b'A') as usize];
+                    *count += 1;
+                    if *count > depth {
+                        return Err(input.error(token, format!("expected {} amphipods of each type", depth)));
+                    }
+                }
+                Some(b'X') => return Err(input.error(token, "expected an amphipod, one of `ABCD`")),
+                Some(&t) if c == t as char => {}
+                _ => return Err(input.error(token, format!("expected `{}`", template))),
+            }
+        }
+        if line.len() < template.len() {
+            return Err(input.missing(line, &format!("`{}`", &template[line.len()..])));
+        }
+    }
+    if !complete {
+        let next = TOP.get(lines.len()).unwrap_or(&BOTTOM);
+        return Err(input.missing(input.text(), &format!("`{}` on the next line", next)));
+    }
+    Ok(lines.iter().map(|l| l.bytes().collect()).collect())
 }
 
 #[aoc(day23, part1)]
@@ -95,6 +137,13 @@ fn part2(input: &[Vec<u8>]) -> usize {
     find_path(&input)
 }
 
+pub(super) fn solutions() -> Vec<Box<dyn Solver>> {
+    vec![
+        Solution::boxed(23, 1, None, parse, part1),
+        Solution::boxed(23, 2, None, parse, part2),
+    ]
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -106,7 +155,7 @@ mod tests {
 ###B#C#B#D###
   #A#D#C#A#
   #########"#;
-        assert_eq!(part1(&parse(input)), 12521);
+        assert_eq!(part1(&parse(input).unwrap()), 12521);
     }
 
     #[test]
@@ -116,6 +165,27 @@ mod tests {
 ###B#C#B#D###
   #A#D#C#A#
   #########"#;
-        assert_eq!(part2(&parse(input)), 44169);
+        assert_eq!(part2(&parse(input).unwrap()), 44169);
+    }
+
+    #[test]
+    fn malformed_input() {
+        let burrow = "#############\n#...........#\n###B#C#B#D###\n  #A#D#C#A#\n  #########";
+        let error = |input: &str| {
+            let err = parse(input).err().unwrap();
+            (err.line, err.column, err.token, err.reason)
+        };
+        let missing = |line, column, reason: &str| (line, column, String::new(), reason.to_string());
+        assert_eq!(error("#"), missing(1, 2, "expected `############`"));
+        assert_eq!(error(""), missing(1, 1, "expected `#############` on the next line"));
+        let truncated = &burrow[..burrow.find("\n  #").unwrap()];
+        assert_eq!(error(truncated), missing(3, 14, "expected `  #########` on the next line"));
+        let shallow = burrow.replace("  #A#D#C#A#\n", "");
+        assert_eq!(error(&shallow), (3, 8, "B".to_string(), "expected 1 amphipods of each type".to_string()));
+        assert_eq!(error(&burrow.replace("#A#D", "#A#A")).3, "expected 2 amphipods of each type");
+        assert_eq!(error(&burrow.replace("#A#D", "#A#.")).1, 6);
+        assert_eq!(error(&burrow.replace("#...", "#.A.")).3, "expected `#...........#`");
+        assert_eq!(error(&format!("{}\n#", burrow)).0, 5);
+        assert!(parse(&burrow.replace("#A#D#C#A#", "#A#D#C#A#  ")).is_ok());
     }
 }
@@ -1,5 +1,7 @@
 use std::collections::VecDeque;
 use itertools::Itertools;
+use crate::parse::{Input, ParseError};
+use super::{Solution, Solver};
 
 #[derive(Eq, PartialEq, Copy, Clone)]
 enum Instruction {
@@ -34,38 +36,109 @@ struct Program {
     subroutines: Vec<Subroutine>
 }
 
-impl <'a> From<&'a str> for Value {
-    fn from(s: &'a str) -> Self {
-        let first_char = s.chars().next().unwrap();
-        if first_char == '-' || first_char.is_ascii_digit() {
-            Value::Literal(s.parse().unwrap())
+impl Value {
+    fn parse(input: &Input, s: &str) -> Result<Self, ParseError> {
+        if s.starts_with('-') || s.starts_with(|c: char| c.is_ascii_digit()) {
+            Ok(Value::Literal(input.parse(s)?))
         } else {
-            Value::Ref(first_char)
+            Ok(Value::Ref(parse_register(input, s)?))
         }
     }
 }
 
+fn parse_register(input: &Input, s: &str) -> Result<char, ParseError> {
+    match input.char(s)? {
+        c @ ('w' | 'x' | 'y' | 'z') => Ok(c),
+        _ => Err(input.error(s, "expected a register (w, x, y or z)")),
+    }
+}
+
+/// Digits of a model number, one per `inp w` block
+const DIGITS: usize = 14;
+
 #[aoc_generator(day24)]
-fn parse(input: &str) -> Program {
+fn parse(input: &str) -> Result<Program, ParseError> {
+    let input = Input::new(24, input);
     let instructions = input.lines().map(|l| {
         let mut split = l.split_whitespace();
-        let first = split.next().unwrap();
-        let second = split.next().unwrap();
-        let third = split.next();
-        match first {
-            "inp" => Instruction::Input,
-            "add" => Instruction::Add(second.chars().next().unwrap(), third.unwrap().into()),
-            "mul" => Instruction::Mul(second.chars().next().unwrap(), third.unwrap().into()),
-            "eql" => Instruction::Eql(second.chars().next().unwrap(), third.unwrap().into()),
-            "div" => Instruction::Div(second.chars().next().unwrap(), third.unwrap().into()),
-            "mod" => Instruction::Mod(second.chars().next().unwrap(), third.unwrap().into()),
-            _ => unreachable!()
+        let first = input.expect(split.next(), l, "an instruction")?;
+        let register = input.expect(split.next(), l, "a register")?;
+        let second = parse_register(&input, register)?;
+        if first == "inp" {
+            if second != 'w' {
+                return Err(input.error(register, "expected `w`, MONAD reads every digit into it"));
+            }
+            return Ok((l, Instruction::Input));
         }
+        let third = Value::parse(&input, input.expect(split.next(), l, "a value")?)?;
+        Ok((l, match first {
+            "add" => Instruction::Add(second, third),
+            "mul" => Instruction::Mul(second, third),
+            "eql" => Instruction::Eql(second, third),
+            "div" => Instruction::Div(second, third),
+            "mod" => Instruction::Mod(second, third),
+            _ => return Err(input.error(first, "unknown instruction"))
+        }))
+    }).collect::<Result<Vec<_>, _>>()?;
+    check_blocks(&input, &instructions)?;
+    let subroutines = instructions.split(|&(_, i)| i == Instruction::Input).map(|i| Subroutine {
+        instructions: i.iter().map(|&(_, i)| i).collect_vec()
     }).collect_vec();
-    let subroutines = instructions.split(|&i| i == Instruction::Input).map(|i| Subroutine {
-        instructions: i.iter().copied().collect_vec()
-    }).collect_vec();
-    Program {subroutines}
+    Ok(Program {subroutines})
+}
+
+/// Checks that the program is made of MONAD's blocks, which the parts solve without running it: each reads a digit,
+/// then either pushes it plus the offset of its last `add y` (`div z 1`), or pops a digit and compares it, plus the
+/// popped offset and the offset of its last `add x`, to the one it read (`div z 26`)
+fn check_blocks(input: &Input, instructions: &[(&str, Instruction)]) -> Result<(), ParseError> {
+    match instructions.first() {
+        None => return Err(input.missing(input.text(), &format!("{} `inp w` blocks", DIGITS))),
+        Some(&(line, i)) if i != Instruction::Input => return Err(input.error(line, "expected `inp w`")),
+        _ => {}
+    }
+    let starts = instructions.iter().positions(|&(_, i)| i == Instruction::Input).collect_vec();
+    if starts.len() > DIGITS {
+        return Err(input.error(instructions[starts[DIGITS]].0, format!("expected {} `inp w` blocks", DIGITS)));
+    }
+    if starts.len() < DIGITS {
+        return Err(input.missing(input.text(), &format!("{} `inp w` blocks", DIGITS)));
+    }
+    let mut pushed: Vec<(&str, isize)> = vec![];
+    for (start, end) in starts.iter().copied().chain(std::iter::once(instructions.len())).tuple_windows() {
+        let (header, block) = (instructions[start].0, &instructions[start + 1..end]);
+        let last_offset = |register| {
+            let last = block.iter().rev().find(|(_, i)| matches!(i, Instruction::Add(r, _) if *r == register));
+            match last {
+                Some(&(_, Instruction::Add(_, Value::Literal(offset)))) => Ok(offset),
+                Some(&(line, _)) => Err(input.error(line, format!("expected `add {} <number>`", register))),
+                None => Err(input.error(header, format!("expected the block to `add {}` a number", register))),
+            }
+        };
+        let mut divisions = block.iter().filter(|(_, i)| matches!(i, Instruction::Div('z', _)));
+        let pops = match divisions.next() {
+            Some((_, Instruction::Div(_, Value::Literal(1)))) => false,
+            Some((_, Instruction::Div(_, Value::Literal(26)))) => true,
+            Some(&(line, _)) => return Err(input.error(line, "expected `div z 1` or `div z 26`")),
+            None => return Err(input.error(header, "expected the block to `div z 1` or `div z 26`")),
+        };
+        if let Some(&(line, _)) = divisions.next() {
+            return Err(input.error(line, "expected a single `div z` in the block"));
+        }
+        let (x_offset, y_offset) = (last_offset('x')?, last_offset('y')?);
+        if !pops {
+            pushed.push((header, y_offset));
+            continue;
+        }
+        let (_, pushed_offset) = pushed.pop().ok_or_else(|| input.error(header, "expected a pushed digit to pop"))?;
+        // The digits differ by the sum of the offsets, and are both between 1 and 9
+        if !x_offset.checked_add(pushed_offset).is_some_and(|difference| (-8..=8).contains(&difference)) {
+            return Err(input.error(header, "expected the block's check to pass for some pair of digits"));
+        }
+    }
+    match pushed.first() {
+        Some(&(header, _)) => Err(input.error(header, "expected the digit pushed by this block to be popped")),
+        None => Ok(()),
+    }
 }
 
 #[aoc(day24, part1)]
@@ -126,4 +199,71 @@ fn part2(input: &Program) -> usize {
         }
     }
     res.into_iter().fold(0usize, |acc, x| acc * 10 + x as usize)
-}
\ No newline at end of file
+}
+
+pub(super) fn solutions() -> Vec<Box<dyn Solver>> {
+    vec![
+        Solution::boxed(24, 1, None, parse, part1),
+        Solution::boxed(24, 2, None, parse, part2),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(div: isize, x: isize, y: isize) -> String {
+        format!("inp w\nmul x 0\nadd x z\nmod x 26\ndiv z {}\nadd x {}\neql x w\neql x 0\nmul y 0\nadd y 25\n\
+                 mul y x\nadd y 1\nmul z y\nmul y 0\nadd y w\nadd y {}\nmul y x\nadd z y", div, x, y)
+    }
+
+    /// Pairs each pushing block with the next one, popping it
+    fn monad(pairs: &[(isize, isize)]) -> String {
+        pairs.iter().flat_map(|&(y, x)| [block(1, 12, y), block(26, x, 4)]).join("\n")
+    }
+
+    #[test]
+    fn model_numbers() {
+        let input = monad(&[(3, -1), (0, 0), (5, -9), (0, 0), (0, 0), (0, 0), (1, 7)]);
+        assert_eq!(part1(&parse(&input).unwrap()), 79_99_95_99_99_99_19);
+        assert_eq!(part2(&parse(&input).unwrap()), 13_11_51_11_11_11_19);
+    }
+
+    #[test]
+    fn malformed_structure() {
+        let error = |input: &str| parse(input).map(|_| ()).map_err(|e| (e.line, e.column, e.reason));
+        let fail = |line, column, reason: &str| Err((line, column, reason.to_string()));
+        assert_eq!(error("inp w\nmul x 0\nadd x z"), fail(3, 8, "expected 14 `inp w` blocks"));
+        assert_eq!(error("mul x 0\ninp w"), fail(1, 1, "expected `inp w`"));
+        assert_eq!(error("inp x"), fail(1, 5, "expected `w`, MONAD reads every digit into it"));
+        let valid = monad(&[(0, 0); 7]);
+        assert!(error(&valid).is_ok());
+        assert_eq!(error(&format!("{}\n{}", valid, block(1, 12, 0))), fail(253, 1, "expected 14 `inp w` blocks"));
+        let pushes = (0..14).map(|_| block(1, 12, 0)).join("\n");
+        assert_eq!(error(&pushes), fail(1, 1, "expected the digit pushed by this block to be popped"));
+        let pops = (0..14).map(|_| block(26, 0, 0)).join("\n");
+        assert_eq!(error(&pops), fail(1, 1, "expected a pushed digit to pop"));
+        let far = monad(&[(0, 0), (5, 4), (0, 0), (0, 0), (0, 0), (0, 0), (0, 0)]);
+        assert_eq!(error(&far), fail(55, 1, "expected the block's check to pass for some pair of digits"));
+        let huge = monad(&[(isize::MAX, 1), (0, 0), (0, 0), (0, 0), (0, 0), (0, 0), (0, 0)]);
+        assert_eq!(error(&huge), fail(19, 1, "expected the block's check to pass for some pair of digits"));
+        let register = valid.replacen("add x 12", "add x w", 1);
+        assert_eq!(error(&register), fail(6, 1, "expected `add x <number>`"));
+        let register = valid.replacen("add y w\nadd y 0", "add y 0\nadd y w", 1);
+        assert_eq!(error(&register), fail(16, 1, "expected `add y <number>`"));
+        let divisor = valid.replacen("div z 1", "div z 2", 1);
+        assert_eq!(error(&divisor), fail(5, 1, "expected `div z 1` or `div z 26`"));
+        let no_div = valid.replacen("div z 1\n", "", 1);
+        assert_eq!(error(&no_div), fail(1, 1, "expected the block to `div z 1` or `div z 26`"));
+        let twice = valid.replacen("div z 1\n", "div z 1\ndiv z 26\n", 1);
+        assert_eq!(error(&twice), fail(6, 1, "expected a single `div z` in the block"));
+    }
+
+    #[test]
+    fn malformed_input() {
+        let err = parse("inp w\nmul x 0\nadd q 1").err().unwrap();
+        assert_eq!((err.line, err.column, err.token.as_str()), (3, 5, "q"));
+        let err = parse("inp w\npow x 2").err().unwrap();
+        assert_eq!((err.line, err.column, err.reason.as_str()), (2, 1, "unknown instruction"));
+    }
+}
@@ -1,79 +1,98 @@
-use fxhash::FxHashMap;
 use crate::days::day25::Cucumber::{East, South};
+use crate::grid::Grid;
+use crate::parse::{Input, ParseError};
+use crate::sim::Simulation;
+use super::{Solution, Solver};
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
-enum Cucumber {
+pub enum Cucumber {
     East,
     South,
     None
 }
 
-#[derive(Clone)]
-struct Grid {
-    positions: FxHashMap<(i32, i32), Cucumber>,
-    len: (i32, i32)
+fn move_cucs(grid: &mut Grid<Cucumber>) -> usize {
+    let mut moved = 0;
+    for ty in ORDER {
+        let check_for_adj = grid.clone();
+        let (dx, dy) = match ty {
+            East => (1, 0),
+            South => (0, 1),
+            Cucumber::None => continue
+        };
+        for (cuc, _) in check_for_adj.iter().filter(|(_, &c)| c == ty) {
+            // The herds wrap around the edges of the map
+            let next_pos = grid.wrap((cuc.0 as isize + dx, cuc.1 as isize + dy));
+            if check_for_adj[next_pos] == Cucumber::None {
+                // move
+                grid[cuc] = Cucumber::None;
+                grid[next_pos] = ty;
+                moved += 1;
+            }
+        }
+    }
+    moved
 }
 
-impl Grid {
-    pub fn from(raw: Vec<Vec<Cucumber>>) -> Self {
-        let y_len = raw.len() as i32;
-        let x_len = raw[0].len() as i32;
-        let positions: FxHashMap<_, _> = raw.into_iter().enumerate()
-            .flat_map(|(y, v)| v.into_iter().enumerate().filter_map(move |(x, c)| match c {
-                Cucumber::None => None,
-                c => Some(((x as i32, y as i32), c))
-            })).collect();
-        Grid {
-            positions,
-            len: (x_len, y_len)
-        }
+static ORDER: [Cucumber; 2] = [East, South];
+
+/// Both herds of sea cucumbers, each moving in turn at each step
+#[derive(Clone, Debug)]
+pub struct Herds {
+    grid: Grid<Cucumber>,
+    generation: usize,
+}
+
+impl Herds {
+    pub fn parse(input: &str) -> Result<Self, ParseError> {
+        parse(input).map(Herds::new)
     }
 
-    fn move_cucs(&mut self) -> usize {
-        let mut moved = 0;
-        for ty in ORDER {
-            let check_for_adj = self.clone();
-            for cuc in check_for_adj.positions.iter().
-                filter_map(|(pos, c)| if *c == ty { Some(*pos) } else { None }) {
-                let next_pos = match ty {
-                    East => ((cuc.0 + 1) % self.len.0, cuc.1),
-                    South => (cuc.0, (cuc.1 + 1) % self.len.1),
-                    Cucumber::None => continue
-                };
-                if !check_for_adj.positions.contains_key(&next_pos) {
-                    // move
-                    self.positions.remove(&cuc);
-                    self.positions.insert(next_pos, ty);
-                    moved += 1;
-                }
-            }
-        }
-        moved
+    fn new(grid: Grid<Cucumber>) -> Self {
+        Herds { grid, generation: 0 }
     }
 }
 
-static ORDER: [Cucumber; 2] = [East, South];
+impl Simulation for Herds {
+    /// Number of sea cucumbers that moved
+    type Step = usize;
+    type Snapshot = Grid<Cucumber>;
+
+    fn step(&mut self) -> usize {
+        self.generation += 1;
+        move_cucs(&mut self.grid)
+    }
+
+    fn generation(&self) -> usize {
+        self.generation
+    }
+
+    fn snapshot(&self) -> Grid<Cucumber> {
+        self.grid.clone()
+    }
+}
 
 #[aoc_generator(day25)]
-fn parse(input: &str) -> Grid {
-    Grid::from(input.lines().map(|s| s.chars().map(|c| match c {
+fn parse(input: &str) -> Result<Grid<Cucumber>, ParseError> {
+    let input = Input::new(25, input);
+    Grid::parse(&input, input.text(), ">v.", |c| match c {
         '>' => East,
         'v' => South,
-        '.' => Cucumber::None,
-        _ => unreachable!()
-    }).collect()).collect())
+        _ => Cucumber::None,
+    })
 }
 
 #[aoc(day25, part1)]
-fn part1(input: &Grid) -> usize {
-    let mut input = input.clone();
-    for i in 1.. {
-        let moved = input.move_cucs();
-        if moved == 0 {
-            return i;
-        }
-    }
-    unreachable!()
+fn part1(input: &Grid<Cucumber>) -> usize {
+    let mut herds = Herds::new(input.clone());
+    while herds.step() != 0 {}
+    herds.generation()
+}
+
+pub(super) fn solutions() -> Vec<Box<dyn Solver>> {
+    vec![
+        Solution::boxed(25, 1, None, parse, part1),
+    ]
 }
 
 #[cfg(test)]
@@ -91,6 +110,6 @@ v>v.vv.v..
 .vv..>.>v.
 v.v..>>v.v
 ....v..v.>"#;
-        assert_eq!(part1(&parse(input)), 58);
+        assert_eq!(part1(&parse(input).unwrap()), 58);
     }
 }
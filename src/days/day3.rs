@@ -1,82 +1,451 @@
-struct Data {
-    numbers: Vec<u32>,
+use std::fmt::{self, Display};
+use std::ops::Range;
+use rayon::prelude::*;
+use crate::bignum::BigUint;
+use crate::parse::{Input, ParseError};
+use super::{Solution, Solver};
+
+/// Reports with more rows than this are counted and sorted in parallel
+const PARALLEL_ROWS: usize = 1 << 14;
+
+/// A row of the diagnostic report. Columns are numbered from the left, the first one being the most significant bit.
+trait Row: Ord + Clone + Send + Sync {
+    /// Widest rows this can hold
+    const CAPACITY: usize;
+
+    fn from_bits(bits: &[bool]) -> Self;
+
+    fn bit(&self, column: usize, width: usize) -> bool;
+
+    /// Adds each set bit to the count of its column
+    fn count_ones(&self, counts: &mut [usize], width: usize);
+
+    fn bits(&self, width: usize) -> Vec<bool> {
+        (0..width).map(|column| self.bit(column, width)).collect()
+    }
+
+    /// The row as written in the report
+    fn text(&self, width: usize) -> String {
+        self.bits(width).iter().map(|&bit| if bit { '1' } else { '0' }).collect()
+    }
+}
+
+macro_rules! int_row {
+    ($($t:ty),*) => {$(
+        impl Row for $t {
+            const CAPACITY: usize = <$t>::BITS as usize;
+
+            fn from_bits(bits: &[bool]) -> Self {
+                bits.iter().fold(0, |n, &bit| n << 1 | bit as $t)
+            }
+
+            fn bit(&self, column: usize, width: usize) -> bool {
+                self >> (width - column - 1) & 1 == 1
+            }
+
+            fn count_ones(&self, counts: &mut [usize], width: usize) {
+                let mut n = *self;
+                while n != 0 {
+                    counts[width - n.trailing_zeros() as usize - 1] += 1;
+                    n &= n - 1;
+                }
+            }
+        }
+    )*};
+}
+
+int_row!(u32, u64, u128);
+
+/// Rows of any width, as 64 bit words starting with the first column. The last word is padded with zeros on the
+/// right, so rows of the same width compare like the numbers they represent.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct BitRow(Vec<u64>);
+
+impl Row for BitRow {
+    const CAPACITY: usize = usize::MAX;
+
+    fn from_bits(bits: &[bool]) -> Self {
+        BitRow(bits.chunks(64).map(|chunk| u64::from_bits(chunk) << (64 - chunk.len())).collect())
+    }
+
+    fn bit(&self, column: usize, _: usize) -> bool {
+        self.0[column / 64] >> (63 - column % 64) & 1 == 1
+    }
+
+    fn count_ones(&self, counts: &mut [usize], _: usize) {
+        for (i, &word) in self.0.iter().enumerate() {
+            let mut word = word;
+            while word != 0 {
+                counts[i * 64 + word.leading_zeros() as usize] += 1;
+                word &= !(1 << (63 - word.leading_zeros()));
+            }
+        }
+    }
+}
+
+struct Diagnostic<R> {
+    rows: Vec<R>,
     /// The length of each entry
-    number_len: usize,
+    width: usize,
 }
 
-#[aoc_generator(day3)]
-fn parse(input: &str) -> Data {
-    let numbers = input
-        .lines()
-        .map(|l| u32::from_str_radix(l, 2).unwrap())
-        .collect();
-    let len = input.lines().next().unwrap().len();
-    Data {
-        numbers,
-        number_len: len,
+impl<R: Row> Diagnostic<R> {
+    fn new(rows: &[Vec<bool>], width: usize) -> Self {
+        Diagnostic { rows: rows.iter().map(|bits| R::from_bits(bits)).collect(), width }
+    }
+
+    /// Number of ones in each column, in a single pass over the rows
+    fn column_counts(&self) -> Vec<usize> {
+        let count = |rows: &[R]| {
+            let mut counts = vec![0; self.width];
+            for row in rows {
+                row.count_ones(&mut counts, self.width);
+            }
+            counts
+        };
+        if self.rows.len() < PARALLEL_ROWS {
+            return count(&self.rows);
+        }
+        self.rows.par_chunks(PARALLEL_ROWS).map(count).reduce(
+            || vec![0; self.width],
+            |mut total, counts| {
+                total.iter_mut().zip(counts).for_each(|(t, c)| *t += c);
+                total
+            },
+        )
+    }
+
+    fn power_consumption(&self) -> String {
+        // Most common is 1 if equal
+        let gamma: Vec<bool> = self.column_counts().iter().map(|&ones| 2 * ones >= self.rows.len()).collect();
+        let epsilon: Vec<bool> = gamma.iter().map(|bit| !bit).collect();
+        product(&gamma, &epsilon)
+    }
+
+    fn sorted(&self) -> Vec<R> {
+        let mut sorted = self.rows.clone();
+        if sorted.len() < PARALLEL_ROWS {
+            sorted.sort_unstable();
+        } else {
+            sorted.par_sort_unstable();
+        }
+        sorted
+    }
+
+    fn life_support(&self) -> Result<String, RatingError> {
+        let sorted = self.sorted();
+        let (oxygen, _) = filter(&sorted, self.width, &Selection::oxygen())?;
+        let (co2, _) = filter(&sorted, self.width, &Selection::co2())?;
+        Ok(product(&sorted[oxygen].bits(self.width), &sorted[co2].bits(self.width)))
+    }
+
+    fn rating(&self, selection: &Selection) -> Result<Rating, RatingError> {
+        let sorted = self.sorted();
+        let (index, steps) = filter(&sorted, self.width, selection)?;
+        let candidates = sorted.iter().map(|row| row.text(self.width)).collect();
+        Ok(Rating { candidates, steps, index })
     }
 }
 
-#[aoc(day3, part1)]
-fn part1(input: &Data) -> u32 {
-    let mut gamma = 0u32;
-    for i in 0..input.number_len {
-        if get_most_common(&input.numbers, i,input.number_len) == 1 {
-            gamma |= 1 << (input.number_len - i - 1);
+/// Bit counts of a column, among the rows left when filtering it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ColumnCounts {
+    pub index: usize,
+    pub zeros: usize,
+    pub ones: usize,
+}
+
+/// Which bit the rows kept at each column have
+pub enum Selection {
+    /// The most common bit, or `tie` when there are as many of both
+    MostCommon { tie: bool },
+    LeastCommon { tie: bool },
+    /// Returns whether to keep the ones
+    Custom(Box<dyn Fn(&ColumnCounts) -> bool + Send + Sync>),
+}
+
+impl Selection {
+    /// The oxygen generator rating's criteria, ties going to 1
+    pub fn oxygen() -> Self {
+        Selection::MostCommon { tie: true }
+    }
+
+    /// The CO2 scrubber rating's criteria, ties going to 0
+    pub fn co2() -> Self {
+        Selection::LeastCommon { tie: false }
+    }
+
+    fn keep_ones(&self, counts: &ColumnCounts) -> bool {
+        match *self {
+            Selection::MostCommon { tie } if counts.ones == counts.zeros => tie,
+            Selection::MostCommon { .. } => counts.ones > counts.zeros,
+            Selection::LeastCommon { tie } if counts.ones == counts.zeros => tie,
+            Selection::LeastCommon { .. } => counts.ones < counts.zeros,
+            Selection::Custom(ref keep_ones) => keep_ones(counts),
         }
     }
-    let epsilon = gamma ^ ((1 << input.number_len) - 1);
-    gamma * epsilon
 }
 
-#[aoc(day3, part2)]
-fn part2(input: &Data) -> u32 {
-    let mut numbers = input.numbers.clone();
-    let mut numbers_clone = input.numbers.clone();
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RatingStep {
+    pub counts: ColumnCounts,
+    /// Bit of the rows kept
+    pub bit: bool,
+    /// Rows left after this column, in [`Rating::candidates`]
+    pub survivors: Range<usize>,
+}
+
+/// The row picked by a [`Selection`], and how it was narrowed down
+#[derive(Clone, Debug)]
+pub struct Rating {
+    /// Every row of the report, sorted
+    pub candidates: Vec<String>,
+    pub steps: Vec<RatingStep>,
+    index: usize,
+}
+
+impl Rating {
+    pub fn value(&self) -> &str {
+        &self.candidates[self.index]
+    }
 
-    reduce_rating(&mut numbers, input.number_len, true);
-    reduce_rating(&mut numbers_clone, input.number_len, false);
-    numbers[0] * numbers_clone[0]
+    /// Rows left after the given step
+    pub fn survivors(&self, step: usize) -> &[String] {
+        &self.candidates[self.steps[step].survivors.clone()]
+    }
+}
+
+/// The selection kept a bit that no row left has
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RatingError {
+    pub counts: ColumnCounts,
+    pub bit: bool,
+    /// The first of the rows left, they all have the other bit in the column
+    pub row: String,
+}
+
+impl Display for RatingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "keeping the rows with a {} in column {} leaves none ({} zeros, {} ones)",
+            self.bit as u8, self.counts.index, self.counts.zeros, self.counts.ones
+        )
+    }
+}
+
+impl std::error::Error for RatingError {}
+
+/// Narrows the rows down column by column until one is left, returning its index and the steps taken.
+///
+/// Rows sharing their first columns are contiguous once sorted, with the ones having a 0 in the next column first,
+/// so each column narrows the range down with a binary search.
+fn filter<R: Row>(sorted: &[R], width: usize, selection: &Selection) -> Result<(usize, Vec<RatingStep>), RatingError> {
+    let mut survivors = 0..sorted.len();
+    let mut steps = vec![];
+    for index in 0..width {
+        if survivors.len() <= 1 {
+            break;
+        }
+        let zeros = sorted[survivors.clone()].partition_point(|row| !row.bit(index, width));
+        let counts = ColumnCounts { index, zeros, ones: survivors.len() - zeros };
+        let bit = selection.keep_ones(&counts);
+        if (if bit { counts.ones } else { counts.zeros }) == 0 {
+            return Err(RatingError { counts, bit, row: sorted[survivors.start].text(width) });
+        }
+        let split = survivors.start + zeros;
+        survivors = if bit { split..survivors.end } else { survivors.start..split };
+        steps.push(RatingStep { counts, bit, survivors: survivors.clone() });
+    }
+    Ok((survivors.start, steps))
+}
+
+/// The narrowest representation fitting the rows
+enum Data {
+    U32(Diagnostic<u32>),
+    U64(Diagnostic<u64>),
+    U128(Diagnostic<u128>),
+    Wide(Diagnostic<BitRow>),
 }
 
-fn reduce_rating(nums: &mut Vec<u32>, num_len: usize, pick_most_common: bool) {
-    let mut i = 0;
-    while nums.len() > 1 {
-        let most_common = get_most_common(nums, i, num_len);
-        let most_common = if pick_most_common { most_common } else { 1 - most_common };
-        nums.retain(|n| ((n >> (num_len - i - 1)) & 1) as u8 == most_common);
-        i += 1;
+macro_rules! dispatch {
+    ($data:expr, $diagnostic:ident => $e:expr) => {
+        match $data {
+            Data::U32($diagnostic) => $e,
+            Data::U64($diagnostic) => $e,
+            Data::U128($diagnostic) => $e,
+            Data::Wide($diagnostic) => $e,
+        }
+    };
+}
+
+/// A diagnostic report, to compute ratings with other criteria than the puzzle's
+pub struct DiagnosticReport(Data);
+
+impl DiagnosticReport {
+    pub fn parse(input: &str) -> Result<Self, ParseError> {
+        parse(input).map(DiagnosticReport)
+    }
+
+    pub fn rating(&self, selection: &Selection) -> Result<Rating, RatingError> {
+        dispatch!(&self.0, d => d.rating(selection))
+    }
+}
+
+impl Data {
+    fn new(rows: &[Vec<bool>], width: usize) -> Self {
+        match width {
+            w if w <= u32::CAPACITY => Data::U32(Diagnostic::new(rows, width)),
+            w if w <= u64::CAPACITY => Data::U64(Diagnostic::new(rows, width)),
+            w if w <= u128::CAPACITY => Data::U128(Diagnostic::new(rows, width)),
+            _ => Data::Wide(Diagnostic::new(rows, width)),
+        }
     }
 }
 
-fn get_most_common(numbers: &[u32], bit: usize, num_len: usize) -> u8 {
-    let bit = num_len - bit - 1;
-    let set_bits = numbers
-        .iter()
-        .copied()
-        .filter(|n| (n >> bit) & 1 == 1)
-        .count();
-    let most = 2 * set_bits;
-    if most >= numbers.len() {
-        1 // Most common is 1 if equal
-    } else {
-        0
+/// Decimal representation of the product of two numbers, given as bits starting with the most significant one
+fn product(a: &[bool], b: &[bool]) -> String {
+    (&BigUint::from_bits(a) * &BigUint::from_bits(b)).to_string()
+}
+
+#[aoc_generator(day3, part1)]
+fn parse(input: &str) -> Result<Data, ParseError> {
+    let input = Input::new(3, input);
+    let width = input.rectangular(input.lines())?;
+    let rows = input
+        .lines()
+        .map(|l| Ok(input.chars(l, "01")?.into_iter().map(|c| c == '1').collect()))
+        .collect::<Result<Vec<Vec<bool>>, ParseError>>()?;
+    Ok(Data::new(&rows, width))
+}
+
+/// Also checks that the life support rating exists, part 2 has no answer otherwise
+#[aoc_generator(day3, part2)]
+fn parse_life_support(input: &str) -> Result<Data, ParseError> {
+    let data = parse(input)?;
+    if let Err(e) = dispatch!(&data, d => d.life_support()) {
+        let input = Input::new(3, input);
+        let line = input.lines().find(|&l| l == e.row).expect("the rows left are from the input");
+        let column = &line[e.counts.index..=e.counts.index];
+        return Err(input.error(column, format!("no life support rating, {}", e)));
     }
+    Ok(data)
+}
+
+#[aoc(day3, part1)]
+fn part1(input: &Data) -> String {
+    dispatch!(input, d => d.power_consumption())
+}
+
+#[aoc(day3, part2)]
+fn part2(input: &Data) -> String {
+    dispatch!(input, d => d.life_support()).expect("checked by the generator")
+}
+
+pub(super) fn solutions() -> Vec<Box<dyn Solver>> {
+    vec![
+        Solution::boxed(3, 1, None, parse, part1),
+        Solution::boxed(3, 2, None, parse_life_support, part2),
+    ]
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
 
     #[test]
     fn part1_example() {
         let input = example!(00100 11110 10110 10111 10101 01111 00111 11100 10000 11001 00010 01010);
-        assert_eq!(part1(&parse(input)), 198);
+        assert_eq!(part1(&parse(input).unwrap()), "198");
     }
 
     #[test]
     fn part2_example() {
         let input = example!(00100 11110 10110 10111 10101 01111 00111 11100 10000 11001 00010 01010);
-        assert_eq!(part2(&parse(input)), 230);
+        assert_eq!(part2(&parse_life_support(input).unwrap()), "230");
+    }
+
+    fn answers<R: Row>(rows: &[Vec<bool>], width: usize) -> (String, Result<String, RatingError>) {
+        let diagnostic = Diagnostic::<R>::new(rows, width);
+        (diagnostic.power_consumption(), diagnostic.life_support())
+    }
+
+    #[test]
+    fn representations_agree() {
+        let mut rng = ChaCha8Rng::seed_from_u64(3);
+        for width in [5, 32, 40, 64, 100] {
+            // Enough rows to be counted in parallel
+            let rows: Vec<Vec<bool>> = (0..PARALLEL_ROWS + 100)
+                .map(|_| (0..width).map(|_| rng.gen()).collect())
+                .collect();
+            let wide = answers::<BitRow>(&rows, width);
+            if width <= 32 {
+                assert_eq!(answers::<u32>(&rows, width), wide);
+            }
+            if width <= 64 {
+                assert_eq!(answers::<u64>(&rows, width), wide);
+                // The same computation as before rows got wider than 32 bits
+                let numbers: Vec<u128> = rows.iter().map(|r| u128::from_bits(r)).collect();
+                let gamma = (0..width).fold(0, |g, c| {
+                    let ones = numbers.iter().filter(|&&n| n >> (width - c - 1) & 1 == 1).count();
+                    g << 1 | (2 * ones >= numbers.len()) as u128
+                });
+                assert_eq!(wide.0, (gamma * (gamma ^ ((1 << width) - 1))).to_string());
+            }
+            assert_eq!(answers::<u128>(&rows, width), wide);
+        }
+    }
+
+    #[test]
+    fn rating_traces() {
+        let input = example!(00100 11110 10110 10111 10101 01111 00111 11100 10000 11001 00010 01010);
+        let report = DiagnosticReport::parse(input).unwrap();
+        let oxygen = report.rating(&Selection::oxygen()).unwrap();
+        assert_eq!(oxygen.value(), "10111");
+        assert_eq!(oxygen.steps.iter().map(|s| s.survivors.len()).collect::<Vec<_>>(), [7, 4, 3, 2, 1]);
+        assert_eq!(oxygen.survivors(2), ["10101", "10110", "10111"]);
+        assert_eq!(oxygen.steps[0].counts, ColumnCounts { index: 0, zeros: 5, ones: 7 });
+        let co2 = report.rating(&Selection::co2()).unwrap();
+        assert_eq!((co2.value(), co2.steps.len()), ("01010", 3));
+
+        let ones = report.rating(&Selection::Custom(Box::new(|c| c.ones > 0))).unwrap();
+        assert_eq!(ones.value(), "11110");
+        let tie_to_zero = report.rating(&Selection::MostCommon { tie: false }).unwrap();
+        assert_eq!(tie_to_zero.value(), "10110");
+
+        let error = DiagnosticReport::parse("10\n11\n11").unwrap().rating(&Selection::co2()).unwrap_err();
+        let counts = ColumnCounts { index: 0, zeros: 0, ones: 3 };
+        assert_eq!(error, RatingError { counts, bit: false, row: "10".to_string() });
+        assert_eq!(error.to_string(), "keeping the rows with a 0 in column 0 leaves none (0 zeros, 3 ones)");
+    }
+
+    #[test]
+    fn wide_rows() {
+        let top = format!("1{}", "0".repeat(129));
+        let input = format!("{}\n{}\n0{}", top, top, "1".repeat(129));
+        let data = parse(&input).unwrap();
+        assert!(matches!(data, Data::Wide(_)));
+        // 2^129 * (2^129 - 1)
+        let expected = "463168356949264781694283940034751631412399373928720379230903586816788982136832";
+        assert_eq!(part1(&data), expected);
+        assert_eq!(part2(&parse_life_support(&input).unwrap()), expected);
+        assert!(matches!(parse(&"1".repeat(64)).unwrap(), Data::U64(_)));
+
+        let err = parse("0101\n0121").err().unwrap();
+        assert_eq!((err.line, err.column, err.token.as_str()), (2, 3, "2"));
+    }
+
+    #[test]
+    fn missing_rating() {
+        // The CO2 scrubber rating keeps 001 and 000, then the 1s of the second column
+        let err = parse_life_support("110\n001\n111\n000").err().unwrap();
+        assert_eq!((err.line, err.column, err.token.as_str()), (4, 2, "0"));
+        let reason = "no life support rating, keeping the rows with a 1 in column 1 leaves none (2 zeros, 0 ones)";
+        assert_eq!(err.reason, reason);
+        // Part 1 doesn't filter the rows
+        assert_eq!(part1(&parse("10\n11").unwrap()), "0");
+        assert!(parse_life_support("10\n11").is_err());
     }
 }
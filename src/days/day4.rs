@@ -1,154 +1,325 @@
-use std::cell::RefCell;
-use std::rc::Rc;
 use fxhash::FxHashMap;
+use rayon::prelude::*;
+use crate::grid::Grid;
+use crate::parse::{Input, ParseError};
+use super::{Solution, Solver};
 
-struct Game {
-    number_map: FxHashMap<u8, Vec<Rc<RefCell<Board>>>>,
-    rolls: Vec<u8>,
+/// Which marked cells make a board win
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WinRule {
+    /// A complete row or column, or diagonal if enabled
+    AnyLine,
+    FullCard,
+    Corners,
 }
 
-struct Board {
-    inner: Vec<BoardNumber>,
-    has_won: bool,
-    row_length: usize,
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rules {
+    pub win: WinRule,
+    /// Whether both diagonals count as lines. Only square boards have them.
+    pub diagonals: bool,
 }
 
-#[derive(Copy, Clone)]
-enum BoardNumber {
-    Marked(u8),
-    Unmarked(u8),
+impl Default for Rules {
+    /// The puzzle's rules, rows and columns only
+    fn default() -> Self {
+        Rules { win: WinRule::AnyLine, diagonals: false }
+    }
 }
 
-impl From<u8> for BoardNumber {
-    fn from(num: u8) -> Self {
-        BoardNumber::Unmarked(num)
-    }
+/// A board winning, after `number` was drawn at index `draw`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Win {
+    pub board: usize,
+    pub draw: usize,
+    pub number: u64,
+    /// Sum of the unmarked numbers times the last number drawn
+    pub score: u128,
+}
+
+/// Draws and rectangular boards of any size. Numbers can appear on several boards, and several times on a board.
+#[derive(Clone, Debug)]
+pub struct Bingo {
+    draws: Vec<u64>,
+    boards: Vec<Grid<u64>>,
+    /// Boards and cell indices of each number
+    cells: FxHashMap<u64, Vec<(usize, usize)>>,
+}
+
+/// Marked cells of a board, as a bitmask, and how many cells of each line are marked
+#[derive(Clone, Debug)]
+struct Marks {
+    cells: Vec<u64>,
+    rows: Vec<usize>,
+    columns: Vec<usize>,
+    diagonals: [usize; 2],
+    total: usize,
+    won: bool,
 }
 
-impl From<BoardNumber> for u8 {
-    fn from(board: BoardNumber) -> Self {
-        match board {
-            BoardNumber::Marked(v) | BoardNumber::Unmarked(v) => v
+impl Marks {
+    fn new(board: &Grid<u64>) -> Self {
+        Marks {
+            cells: vec![0; board.len().div_ceil(64)],
+            rows: vec![0; board.height()],
+            columns: vec![0; board.width()],
+            diagonals: [0; 2],
+            total: 0,
+            won: false,
+        }
+    }
+
+    fn is_marked(&self, index: usize) -> bool {
+        self.cells[index / 64] & (1 << (index % 64)) != 0
+    }
+
+    fn mark(&mut self, board: &Grid<u64>, index: usize) {
+        if self.is_marked(index) {
+            return;
+        }
+        let width = board.width();
+        let (x, y) = (index % width, index / width);
+        let square = width == board.height();
+        self.cells[index / 64] |= 1 << (index % 64);
+        self.total += 1;
+        self.rows[y] += 1;
+        self.columns[x] += 1;
+        if square && x == y {
+            self.diagonals[0] += 1;
+        }
+        if square && x + y == width - 1 {
+            self.diagonals[1] += 1;
         }
     }
-}
 
-impl<S: AsRef<str>> From<S> for Board {
-    fn from(string: S) -> Self {
-        let len = string.as_ref().lines().next().unwrap().split_whitespace().count();
-        let inner = string.as_ref()
-            .lines()
-            .flat_map(|s| s.split_whitespace())
-            .map(|s| BoardNumber::from(s.parse::<u8>().unwrap()))
-            .collect();
-        Board {
-            inner,
-            has_won: false,
-            row_length: len,
+    /// Whether the board wins, after marking a cell. Only the lines going through the cell are checked.
+    fn wins(&self, board: &Grid<u64>, index: usize, rules: Rules) -> bool {
+        let (width, height) = (board.width(), board.height());
+        let (x, y) = (index % width, index / width);
+        let square = width == height;
+        match rules.win {
+            WinRule::AnyLine => {
+                let diagonal = |d: usize, on: bool| rules.diagonals && square && on && self.diagonals[d] == width;
+                self.rows[y] == width
+                    || self.columns[x] == height
+                    || diagonal(0, x == y)
+                    || diagonal(1, x + y == width - 1)
+            }
+            WinRule::FullCard => self.total == board.len(),
+            WinRule::Corners => [0, width - 1, board.len() - width, board.len() - 1].iter().all(|&i| self.is_marked(i)),
         }
     }
+
+    fn unmarked_sum(&self, board: &Grid<u64>) -> u128 {
+        board.cells().iter().enumerate().filter(|&(i, _)| !self.is_marked(i)).map(|(_, &n)| n as u128).sum()
+    }
 }
 
-impl BoardNumber {
-    pub fn is_marked(&self) -> bool {
-        match self {
-            BoardNumber::Marked(_) => true,
-            BoardNumber::Unmarked(_) => false
+impl Bingo {
+    pub fn parse(input: &str) -> Result<Self, ParseError> {
+        let input = Input::new(4, input);
+        let mut split = input.text().split("\n\n");
+
+        let draws = input.parse_list(split.next().unwrap_or_default(), ',')?;
+        let boards: Vec<_> = split.map(|s| parse_board(&input, s)).collect::<Result<_, _>>()?;
+
+        let mut cells: FxHashMap<_, Vec<_>> = FxHashMap::default();
+        for (i, board) in boards.iter().enumerate() {
+            for (index, &number) in board.cells().iter().enumerate() {
+                cells.entry(number).or_default().push((i, index));
+            }
         }
+        Ok(Bingo { draws, boards, cells })
     }
-}
 
-impl Board {
-    pub fn mark(&mut self, num: u8) -> bool {
-        let mut index = 0;
-        for (i, old) in self.inner.iter_mut().enumerate() {
-            let val: u8 = (*old).into();
-            if val == num {
-                *old = BoardNumber::Marked(num);
-                index = i;
+    pub fn draws(&self) -> &[u64] {
+        &self.draws
+    }
+
+    pub fn boards(&self) -> &[Grid<u64>] {
+        &self.boards
+    }
+
+    /// The boards in the order they win. Boards stop playing once they have won.
+    pub fn play(&self, rules: Rules) -> Vec<Win> {
+        self.play_order(&self.draws, rules)
+    }
+
+    /// Same as [`Bingo::play`], drawing `order` instead of the input's numbers
+    pub fn play_order(&self, order: &[u64], rules: Rules) -> Vec<Win> {
+        let mut state = GameState::new(self, rules);
+        let mut wins = vec![];
+        for &number in order {
+            if state.all_won() {
                 break;
             }
-        };
-        let row = index / self.row_length;
-        let col = index % self.row_length;
-
-        if self.check_col(col) || self.check_row(row) {
-            self.has_won = true;
-            true
-        } else {
-            false
+            wins.extend(state.draw(number));
         }
+        wins
     }
 
-    fn check_col(&self, col: usize) -> bool {
-        self.inner.iter().skip(col).step_by(self.row_length).all(|b| b.is_marked())
+    /// Plays every draw order in parallel
+    pub fn play_orders(&self, orders: &[Vec<u64>], rules: Rules) -> Vec<Vec<Win>> {
+        orders.par_iter().map(|order| self.play_order(order, rules)).collect()
     }
 
-    fn check_row(&self, row: usize) -> bool {
-        self.inner.iter().skip(row * self.row_length).take(self.row_length).all(|b| b.is_marked())
+    /// The timeline of the game, lazily drawing numbers as it is iterated
+    pub fn events(&self, rules: Rules) -> Events<'_> {
+        Events { state: GameState::new(self, rules), cell: 0, remaining: 0 }
     }
 }
 
-#[aoc_generator(day4)]
-fn parse(input: &str) -> Game {
-    let mut split = input.split("\n\n");
+/// The marked cells of every board during a game. The parsed [`Bingo`] is never modified, so any number of games can
+/// be played from it, and states cloned to try different draws from the same point.
+#[derive(Clone, Debug)]
+pub struct GameState<'a> {
+    bingo: &'a Bingo,
+    rules: Rules,
+    marks: Vec<Marks>,
+    drawn: usize,
+    won: usize,
+}
 
-    let rolls = split.next().unwrap().split(',').map(|s| s.parse().unwrap()).collect();
-    let boards: Vec<_> = split.map(|s| Rc::new(RefCell::new(Board::from(s)))).collect();
+impl<'a> GameState<'a> {
+    pub fn new(bingo: &'a Bingo, rules: Rules) -> Self {
+        let marks = bingo.boards.iter().map(Marks::new).collect();
+        GameState { bingo, rules, marks, drawn: 0, won: 0 }
+    }
+
+    /// How many numbers were drawn so far
+    pub fn drawn(&self) -> usize {
+        self.drawn
+    }
 
-    let mut map = FxHashMap::default();
-    for board in boards {
-        for num in &(*(*board).borrow()).inner {
-            let num = (*num).into();
-            map.entry(num).or_insert_with(|| Vec::with_capacity(5 * 5)).push(Rc::clone(&board));
+    pub fn has_won(&self, board: usize) -> bool {
+        self.marks[board].won
+    }
+
+    pub fn all_won(&self) -> bool {
+        self.won == self.marks.len()
+    }
+
+    /// Draws a number, returning the boards it makes win
+    pub fn draw(&mut self, number: u64) -> Vec<Win> {
+        let mut wins = vec![];
+        let mut cell = 0;
+        while cell < self.cells(number).len() {
+            let (next, win) = self.mark(number, cell);
+            wins.extend(win);
+            cell = next;
         }
+        self.drawn += 1;
+        wins
     }
 
-    Game { rolls, number_map: map }
-}
+    fn cells(&self, number: u64) -> &'a [(usize, usize)] {
+        self.bingo.cells.get(&number).map_or(&[], Vec::as_slice)
+    }
 
-#[aoc(day4, part1)]
-fn part1(input: &Game) -> u32 {
-    for num in &input.rolls {
-        let boards = &input.number_map[num];
-        for board in boards {
-            if board.borrow_mut().mark(*num) {
-                let unmarked: u32 = sum_unmarked(&(**board).borrow());
-                return unmarked * (*num as u32);
-            }
+    /// Marks every cell holding `number` on the board of the `cell`th one, before checking whether the board wins.
+    /// Returns the first cell of the next board, and the win if the board had not won yet and now does.
+    fn mark(&mut self, number: u64, cell: usize) -> (usize, Option<Win>) {
+        let cells = self.cells(number);
+        let board = cells[cell].0;
+        // Cells are sorted by board
+        let end = cell + cells[cell..].iter().take_while(|&&(b, _)| b == board).count();
+        let grid = &self.bingo.boards[board];
+        let marks = &mut self.marks[board];
+        if marks.won {
+            return (end, None);
         }
+        cells[cell..end].iter().for_each(|&(_, index)| marks.mark(grid, index));
+        if !cells[cell..end].iter().any(|&(_, index)| marks.wins(grid, index, self.rules)) {
+            return (end, None);
+        }
+        marks.won = true;
+        self.won += 1;
+        let score = marks.unmarked_sum(grid) * number as u128;
+        (end, Some(Win { board, draw: self.drawn, number, score }))
     }
-    unreachable!()
 }
 
-#[aoc(day4, part2)]
-fn part2(input: &Game) -> u32 {
-    let mut last_score = 0;
-    for num in &input.rolls {
-        let boards = &input.number_map[num];
-        for board in boards {
-            if (**board).borrow().has_won {
-                continue;
-            }
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event {
+    Won(Win),
+    /// Every number was drawn without the board winning
+    NeverWon { board: usize },
+}
+
+/// Every board winning in order, then the boards that never won by index
+pub struct Events<'a> {
+    state: GameState<'a>,
+    /// The next cell to mark of the number being drawn
+    cell: usize,
+    /// The next board to check once the game is over
+    remaining: usize,
+}
+
+impl Iterator for Events<'_> {
+    type Item = Event;
 
-            if board.borrow_mut().mark(*num) {
-                let unmarked: u32 = sum_unmarked(&(**board).borrow());
-                last_score = unmarked * (*num as u32);
+    fn next(&mut self) -> Option<Event> {
+        let draws = &self.state.bingo.draws;
+        while !self.state.all_won() && self.state.drawn < draws.len() {
+            let number = draws[self.state.drawn];
+            while self.cell < self.state.cells(number).len() {
+                let (next, win) = self.state.mark(number, self.cell);
+                self.cell = next;
+                if let Some(win) = win {
+                    return Some(Event::Won(win));
+                }
             }
+            self.state.drawn += 1;
+            self.cell = 0;
         }
+        let marks = &self.state.marks;
+        let board = (self.remaining..marks.len()).find(|&b| !marks[b].won)?;
+        self.remaining = board + 1;
+        Some(Event::NeverWon { board })
+    }
+}
+
+fn parse_board(input: &Input, string: &str) -> Result<Grid<u64>, ParseError> {
+    let len = string.lines().next().unwrap_or_default().split_whitespace().count();
+    if len == 0 {
+        return Err(input.missing(string, "a board"));
+    }
+    if let Some(line) = string.lines().find(|l| l.split_whitespace().count() != len) {
+        return Err(input.error(line, format!("expected a row of {} numbers", len)));
+    }
+    let rows = string
+        .lines()
+        .map(|l| l.split_whitespace().map(|s| input.parse(s)).collect())
+        .collect::<Result<_, _>>()?;
+    Ok(Grid::from_rows(rows))
+}
+
+/// Also checks that a board wins, the parts have no answer otherwise
+#[aoc_generator(day4)]
+fn parse(input: &str) -> Result<Bingo, ParseError> {
+    let bingo = Bingo::parse(input)?;
+    if bingo.play(Rules::default()).is_empty() {
+        let input = Input::new(4, input);
+        return Err(input.error(input.text().lines().next().unwrap_or_default(), "no board wins with these draws"));
     }
-    last_score
+    Ok(bingo)
+}
+
+#[aoc(day4, part1)]
+fn part1(input: &Bingo) -> u128 {
+    input.play(Rules::default()).first().expect("checked by the generator").score
 }
 
-#[inline(always)]
-fn sum_unmarked(board: &Board) -> u32 {
-    board.inner.iter()
-        .filter(|b| !b.is_marked())
-        .map(|num| {
-            let num: u8 = (*num).into();
-            num as u32
-        })
-        .sum()
+#[aoc(day4, part2)]
+fn part2(input: &Bingo) -> u128 {
+    input.play(Rules::default()).last().expect("checked by the generator").score
+}
+
+pub(super) fn solutions() -> Vec<Box<dyn Solver>> {
+    vec![
+        Solution::boxed(4, 1, None, parse, part1),
+        Solution::boxed(4, 2, None, parse, part2),
+    ]
 }
 
 #[cfg(test)]
@@ -176,7 +347,7 @@ mod tests {
 18  8 23 26 20
 22 11 13  6  5
  2  0 12  3  7"#;
-        assert_eq!(part1(&parse(input)), 4512);
+        assert_eq!(part1(&parse(input).unwrap()), 4512);
     }
 
     #[test]
@@ -200,6 +371,95 @@ mod tests {
 18  8 23 26 20
 22 11 13  6  5
  2  0 12  3  7"#;
-        assert_eq!(part2(&parse(input)), 1924);
+        assert_eq!(part2(&parse(input).unwrap()), 1924);
+    }
+
+    #[test]
+    fn win_rules() {
+        let bingo = Bingo::parse("1,5,9,2,3,7,4,6,8\n\n1 2 3\n4 5 6\n7 8 9").unwrap();
+        let first = |win, diagonals| {
+            let w = bingo.play(Rules { win, diagonals })[0];
+            (w.draw, w.number, w.score)
+        };
+        assert_eq!(first(WinRule::AnyLine, true), (2, 9, 270));
+        assert_eq!(first(WinRule::AnyLine, false), (4, 3, 75));
+        assert_eq!(first(WinRule::Corners, false), (5, 7, (4 + 6 + 8) * 7));
+        assert_eq!(first(WinRule::FullCard, false), (8, 8, 0));
+    }
+
+    #[test]
+    fn rectangular_boards_and_wide_numbers() {
+        let bingo = Bingo::parse("99,70000000000,6,4\n\n1000 70000000000\n5 6\n300 4\n\n4 5 6\n7 8 9").unwrap();
+        assert_eq!((bingo.boards()[0].width(), bingo.boards()[0].height()), (2, 3));
+        let wins = bingo.play(Rules { win: WinRule::AnyLine, diagonals: true });
+        assert_eq!(wins, [Win { board: 0, draw: 3, number: 4, score: 1305 * 4 }]);
+    }
+
+    #[test]
+    fn timeline() {
+        let bingo = Bingo::parse("1,2,3,4\n\n1 2\n3 4\n\n9 9\n9 9\n\n3 4\n5 6\n\n5 6\n7 8").unwrap();
+        let events: Vec<_> = bingo.events(Rules::default()).collect();
+        assert_eq!(events, [
+            Event::Won(Win { board: 0, draw: 1, number: 2, score: 7 * 2 }),
+            Event::Won(Win { board: 2, draw: 3, number: 4, score: 11 * 4 }),
+            Event::NeverWon { board: 1 },
+            Event::NeverWon { board: 3 },
+        ]);
+        // Stops drawing once every board has won
+        let bingo = Bingo::parse("1,2,3\n\n1 2").unwrap();
+        let mut events = bingo.events(Rules::default());
+        assert_eq!(events.next(), Some(Event::Won(Win { board: 0, draw: 0, number: 1, score: 2 })));
+        assert_eq!(events.next(), None);
+    }
+
+    #[test]
+    fn repeated_numbers() {
+        // Both 5s are marked before the score is counted
+        let bingo = parse("1,5\n\n5 1\n2 5").unwrap();
+        assert_eq!(bingo.play(Rules::default()), [Win { board: 0, draw: 1, number: 5, score: 2 * 5 }]);
+        assert_eq!(bingo.events(Rules::default()).next(), Some(Event::Won(bingo.play(Rules::default())[0])));
+        let mut state = GameState::new(&bingo, Rules::default());
+        assert!(state.draw(1).is_empty());
+        assert_eq!(state.draw(5)[0].score, 10);
+        assert_eq!(part1(&bingo), 10);
+
+        // A number filling a line by itself wins the board once
+        let bingo = parse("7\n\n7 7\n1 2").unwrap();
+        assert_eq!(bingo.play(Rules::default()), [Win { board: 0, draw: 0, number: 7, score: 3 * 7 }]);
+    }
+
+    #[test]
+    fn independent_games() {
+        let bingo = Bingo::parse(&crate::gen::generate(4, 5, 3).unwrap()).unwrap();
+        let (first, last) = (part1(&bingo), part2(&bingo));
+        assert_eq!((part1(&bingo), part2(&bingo)), (first, last));
+
+        let mut state = GameState::new(&bingo, Rules::default());
+        for &number in &bingo.draws()[..5] {
+            assert!(state.draw(number).is_empty());
+        }
+        let mut branch = state.clone();
+        let rest = &bingo.draws()[5..];
+        let wins: Vec<_> = rest.iter().flat_map(|&n| branch.draw(n)).collect();
+        assert_eq!(wins, bingo.play(Rules::default()));
+        assert_eq!((state.drawn(), branch.drawn()), (5, bingo.draws().len()));
+        assert!(!state.has_won(wins[0].board) && branch.all_won());
+
+        let mut orders = vec![bingo.draws().to_vec(), bingo.draws().iter().rev().copied().collect()];
+        orders.push(bingo.draws().iter().copied().filter(|n| n % 2 == 0).collect());
+        let parallel = bingo.play_orders(&orders, Rules::default());
+        for (order, wins) in orders.iter().zip(parallel) {
+            assert_eq!(wins, bingo.play_order(order, Rules::default()));
+        }
+    }
+
+    #[test]
+    fn malformed_input() {
+        let err = parse("7,4,x\n\n1 2\n3 4").err().unwrap();
+        assert_eq!((err.line, err.column, err.token.as_str()), (1, 5, "x"));
+        let err = parse("7,4\n\n1 2\n3 4\n5").err().unwrap();
+        assert_eq!((err.line, err.column, err.reason.as_str()), (5, 1, "expected a row of 2 numbers"));
+        let err = parse("7,4\n\n1 2\n3 5").err().unwrap();
+        assert_eq!((err.line, err.token.as_str(), err.reason.as_str()), (1, "7,4", "no board wins with these draws"));
     }
 }
\ No newline at end of file
@@ -1,7 +1,12 @@
-use std::cmp::Ordering;
+use std::fmt::{self, Display};
+use std::str::FromStr;
 
 use fxhash::{FxHashMap, FxHashSet};
 use regex::Regex;
+use crate::grid::Grid;
+use crate::parse::{Input, ParseError};
+use crate::segments::{self, Segment};
+use super::{Solution, Solver};
 
 #[derive(Copy, Clone, Hash, Eq, PartialEq, Debug)]
 struct Point {
@@ -15,13 +20,16 @@ struct Line {
     end: Point,
     dx: i32,
     dy: i32,
+    /// Step between two consecutive points with integer coordinates, the smallest one along the line
     slope: (i32, i32),
-    _length_sq: i32
+    /// Number of steps from the start to the end
+    steps: i32,
+    length_sq: i64
 }
 
 impl Line {
     /// Uses vectors to calculate whether the line points and the given points are colinear - slower but low memory footprint
-    fn _vector_intersection(&self, line: &Line, full: bool, intersections: &mut FxHashSet<Point>) {
+    fn vector_intersection(&self, line: &Line, full: bool, intersections: &mut FxHashSet<Point>) {
         if std::ptr::eq(line, self) {
             return;
         }
@@ -35,18 +43,40 @@ impl Line {
             }
         }
 
-        let mut point = Point { x: line.start.x, y: line.start.y };
+        intersections.extend(line.walk().filter(|point| self.contains_vectored(point)));
+    }
 
-        loop {
-            if self._contains_vectored(&point) {
-                intersections.insert(point);
-            }
-            if point.x == line.end.x && point.y == line.end.y {
-                break;
+    /// The points with integer coordinates on this line, without allocating them
+    fn walk(&self) -> impl Iterator<Item = Point> + '_ {
+        (0..=self.steps).map(move |i| Point { x: self.start.x + i * self.slope.0, y: self.start.y + i * self.slope.1 })
+    }
+
+    /// The points of [`Line::walk`] inside `region`, skipping straight to the first one
+    fn walk_within(&self, region: Region) -> impl Iterator<Item = Point> + '_ {
+        let (mut first, mut last) = (0, self.steps as i64);
+        let x = (self.start.x, self.slope.0, region.min.0, region.max.0);
+        let y = (self.start.y, self.slope.1, region.min.1, region.max.1);
+        for (start, step, min, max) in [x, y] {
+            // The steps `i` with `low <= i * step <= high`
+            let (low, high) = (min as i64 - start as i64, max as i64 - start as i64);
+            let (step, low, high) = if step < 0 { (-step as i64, -high, -low) } else { (step as i64, low, high) };
+            if step == 0 {
+                if low > 0 || high < 0 {
+                    last = -1;
+                }
+            } else {
+                first = first.max(-(-low).div_euclid(step));
+                last = last.min(high.div_euclid(step));
             }
-            point.x += line.slope.0;
-            point.y += line.slope.1;
         }
+        (first..=last).map(move |i| {
+            let i = i as i32;
+            Point { x: self.start.x + i * self.slope.0, y: self.start.y + i * self.slope.1 }
+        })
+    }
+
+    fn is_diagonal(&self) -> bool {
+        self.dx != 0 && self.dy != 0
     }
 
     /// Gets the list of points covered by this line to calculate an intersection - faster but requires more memory
@@ -55,35 +85,29 @@ impl Line {
             return None;
         }
 
-        let mut points = Vec::with_capacity((self.dx + self.dy).abs() as usize);
-        let mut point = Point { x: self.start.x, y: self.start.y };
-
-        loop {
-            points.push(point);
-            if point.x == self.end.x && point.y == self.end.y {
-                break;
-            }
-            point.x += self.slope.0;
-            point.y += self.slope.1;
-        }
-
-        Some(points)
+        Some(self.walk().collect())
     }
 
-    fn _contains_vectored(&self, point: &Point) -> bool {
+    fn contains_vectored(&self, point: &Point) -> bool {
         let point = *point;
         if (point == self.start) || (point == self.end) {
             return true;
         }
-        let cross_product = self.dx * (point.y - self.start.y) - (point.x - self.start.x) * self.dy;
+        // A single point line only contains itself, and every point would pass the tests below
+        if self.length_sq == 0 {
+            return false;
+        }
+        let (dx, dy) = (self.dx as i64, self.dy as i64);
+        let (px, py) = ((point.x - self.start.x) as i64, (point.y - self.start.y) as i64);
+        let cross_product = dx * py - px * dy;
         if cross_product != 0 {
             return false;
         }
-        let dot_product = (point.x - self.start.x) * self.dx + (point.y - self.start.y) * self.dy;
+        let dot_product = px * dx + py * dy;
         if dot_product < 0 {
             return false;
         }
-        dot_product <= self._length_sq
+        dot_product <= self.length_sq
     }
 }
 
@@ -98,82 +122,299 @@ impl From<((i32, i32), (i32, i32))> for Line {
             y: points.1.1,
         };
 
-        let len = (end.x - start.x).pow(2) + (end.y - start.y).pow(2);
+        let len = ((end.x - start.x) as i64).pow(2) + ((end.y - start.y) as i64).pow(2);
 
         let dx = end.x - start.x;
         let dy = end.y - start.y;
 
-        let slope = (match dx.cmp(&0) {
-            Ordering::Less => -1,
-            Ordering::Equal => 0,
-            Ordering::Greater => 1
-        }, match dy.cmp(&0) {
-            Ordering::Less => -1,
-            Ordering::Equal => 0,
-            Ordering::Greater => 1
-        });
+        // 1 for horizontal, vertical and 45° lines, lines of other slopes skip the points between two integer ones
+        let steps = segments::gcd(dx.unsigned_abs() as u128, dy.unsigned_abs() as u128) as i32;
+        let slope = if steps == 0 { (0, 0) } else { (dx / steps, dy / steps) };
 
-        Line { start, end, slope, dx, dy, _length_sq: len }
+        Line { start, end, slope, steps, dx, dy, length_sq: len }
     }
 }
 
 
-#[aoc_generator(day5)]
-fn parse(input: &str) -> Vec<Line> {
-    let regex = Regex::new(r#"(\d+),(\d+) -> (\d+),(\d+)"#).unwrap();
-    input.lines().map(|l| {
-        let caps = regex.captures(l).unwrap();
-        ((caps[1].parse().unwrap(), caps[2].parse().unwrap()), (caps[3].parse().unwrap(), caps[4].parse().unwrap())).into()
-    }).collect()
+/// How overlapping points are counted, only at integer coordinates. Lines can have any slope.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Strategy {
+    /// Counts every point of every line in a hash map, its memory grows with the total length of the lines
+    Points,
+    /// Counts in a byte per point of the bounding box, the fastest while the box stays small. Boxes with more than
+    /// [`Heatmap::MAX_POINTS`] are counted like [`Strategy::Sweep`] instead, their lines can be too long to store
+    /// their points.
+    Dense,
+    /// Tests the points of each line against every other line, only storing the overlaps
+    Vectors,
+    /// Exact sweep over the lines, which never visits their points, see [`crate::segments`]
+    Sweep,
 }
 
-/*
-#[aoc(day5, part1, vectors)]
-fn part1_vectors(input: &[Line]) -> usize {
-    let mut intersections = FxHashSet::default();
-    for line in input {
-        for other in input {
-            line.vector_intersection(other, false, &mut intersections);
+impl Strategy {
+    pub const ALL: [Strategy; 4] = [Strategy::Points, Strategy::Dense, Strategy::Vectors, Strategy::Sweep];
+
+    /// Name of the solution variant using the strategy
+    pub fn name(self) -> &'static str {
+        match self {
+            Strategy::Points => "points",
+            Strategy::Dense => "dense",
+            Strategy::Vectors => "vectors",
+            Strategy::Sweep => "sweep",
         }
     }
-    intersections.len()
 }
-*/
 
-#[aoc(day5, part1, points)]
-fn part1_points(input: &[Line]) -> usize {
-    let mut intersections: FxHashMap<Point, u8> = FxHashMap::default();
-    for line in input {
-        for point in line.get_points(true).into_iter().flatten() {
-            *intersections.entry(point).or_insert(0) += 1;
+/// The lines of hydrothermal vents
+pub struct Vents(Vec<Line>);
+
+impl Vents {
+    pub fn parse(input: &str) -> Result<Self, ParseError> {
+        parse(input).map(Vents)
+    }
+
+    /// The number of points covered by at least two lines, ignoring diagonal lines unless `diagonals` is set
+    pub fn overlaps(&self, strategy: Strategy, diagonals: bool) -> usize {
+        overlaps(&self.0, strategy, diagonals)
+    }
+
+    /// How many lines cover each point of `region`, or of the smallest region holding every line. Fails when the
+    /// region has more than [`Heatmap::MAX_POINTS`].
+    pub fn heatmap(&self, diagonals: bool, region: Option<Region>) -> Result<Heatmap, RegionTooLarge> {
+        let lines: Vec<_> = self.0.iter().filter(|l| diagonals || !l.is_diagonal()).collect();
+        let region = region.unwrap_or_else(|| {
+            let ends = || lines.iter().flat_map(|l| [l.start, l.end]);
+            let (xs, ys) = (|| ends().map(|p| p.x), || ends().map(|p| p.y));
+            Region {
+                min: (xs().min().unwrap_or(0), ys().min().unwrap_or(0)),
+                max: (xs().max().unwrap_or(0), ys().max().unwrap_or(0)),
+            }
+        });
+        let (width, height) = region.size();
+        if width.checked_mul(height).is_none_or(|points| points > Heatmap::MAX_POINTS) {
+            return Err(RegionTooLarge { width, height });
+        }
+        let mut counts = Grid::from_fn(width as usize, height as usize, |_| 0u8);
+        for point in lines.iter().flat_map(|l| l.walk_within(region)) {
+            let cell = (point.x - region.min.0) as usize + (point.y - region.min.1) as usize * width as usize;
+            counts.cells_mut()[cell] = counts.cells()[cell].saturating_add(1);
         }
+        Ok(Heatmap { origin: region.min, counts })
     }
-    intersections.into_iter().filter(|(_, v)| *v > 1).count()
 }
 
-#[aoc(day5, part2, points)]
-fn part2_points(input: &[Line]) -> usize {
-    let mut intersections: FxHashMap<Point, u8> = FxHashMap::default();
+/// A rectangle of the ocean floor, both corners included
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Region {
+    pub min: (i32, i32),
+    pub max: (i32, i32),
+}
+
+impl Region {
+    /// Width and height, in points
+    pub fn size(&self) -> (u64, u64) {
+        let side = |min: i32, max: i32| (max as i64 - min as i64 + 1) as u64;
+        (side(self.min.0, self.max.0), side(self.min.1, self.max.1))
+    }
+}
+
+impl FromStr for Region {
+    type Err = String;
+
+    /// Parses two opposite corners, as `x1,y1,x2,y2`
+    fn from_str(s: &str) -> Result<Self, String> {
+        let numbers: Vec<i32> = s
+            .split(',')
+            .map(|n| n.trim().parse().map_err(|_| format!("`{}` is not a coordinate", n)))
+            .collect::<Result<_, _>>()?;
+        match numbers[..] {
+            [x1, y1, x2, y2] => Ok(Region { min: (x1.min(x2), y1.min(y2)), max: (x1.max(x2), y1.max(y2)) }),
+            _ => Err(format!("expected `x1,y1,x2,y2`, got `{}`", s)),
+        }
+    }
+}
+
+/// How many lines cover each point of a region, saturating at 255
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Heatmap {
+    /// Coordinates of the top left point
+    pub origin: (i32, i32),
+    pub counts: Grid<u8>,
+}
+
+/// A heatmap region with more than [`Heatmap::MAX_POINTS`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RegionTooLarge {
+    pub width: u64,
+    pub height: u64,
+}
+
+impl Display for RegionTooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "a heatmap of {}x{} points is over the limit of {} points, pick a smaller region",
+            self.width, self.height, Heatmap::MAX_POINTS
+        )
+    }
+}
+
+impl std::error::Error for RegionTooLarge {}
+
+impl Heatmap {
+    /// Largest number of points in a heatmap, each takes a byte
+    pub const MAX_POINTS: u64 = 1 << 27;
+
+    pub fn max(&self) -> u8 {
+        self.counts.cells().iter().copied().max().unwrap_or(0)
+    }
+
+    /// One row of comma separated counts per line of the region
+    pub fn to_csv(&self) -> String {
+        self.counts.rows().map(|row| row.iter().map(|c| c.to_string()).collect::<Vec<_>>().join(",") + "\n").collect()
+    }
+
+    /// Binary PGM (`P5`) encoding, the most covered points white
+    pub fn to_pgm(&self) -> Vec<u8> {
+        let max = self.max().max(1) as u32;
+        let mut buf = format!("P5\n{} {}\n255\n", self.counts.width(), self.counts.height()).into_bytes();
+        buf.extend(self.counts.cells().iter().map(|&c| (c as u32 * 255 / max) as u8));
+        buf
+    }
+}
+
+fn overlaps(input: &[Line], strategy: Strategy, diagonals: bool) -> usize {
+    match strategy {
+        Strategy::Points => points(input, diagonals),
+        Strategy::Dense => dense(input, diagonals),
+        Strategy::Vectors => vectors(input, diagonals),
+        Strategy::Sweep => sweep(input, diagonals),
+    }
+}
+
+/// How many lines cover each point covered at all
+fn counts(input: &[Line], diagonals: bool) -> FxHashMap<Point, u8> {
+    let mut counts: FxHashMap<Point, u8> = FxHashMap::default();
     for line in input {
-        for point in line.get_points(false).into_iter().flatten() {
-            *intersections.entry(point).or_insert(0) += 1;
+        for point in line.get_points(!diagonals).into_iter().flatten() {
+            let count = counts.entry(point).or_insert(0);
+            *count = count.saturating_add(1);
         }
     }
-    intersections.into_iter().filter(|(_, v)| *v > 1).count()
+    counts
 }
 
-/*
-#[aoc(day5, part2, vectors)]
-fn part2_vectors(input: &[Line]) -> usize {
+fn points(input: &[Line], diagonals: bool) -> usize {
+    counts(input, diagonals).into_values().filter(|&v| v > 1).count()
+}
+
+fn dense(input: &[Line], diagonals: bool) -> usize {
+    let lines: Vec<_> = input.iter().filter(|l| diagonals || !l.is_diagonal()).collect();
+    let ends = || lines.iter().flat_map(|l| [l.start, l.end]);
+    let (Some(min_x), Some(max_x)) = (ends().map(|p| p.x).min(), ends().map(|p| p.x).max()) else {
+        return 0;
+    };
+    let (min_y, max_y) = (ends().map(|p| p.y).min().unwrap(), ends().map(|p| p.y).max().unwrap());
+    let (width, height) = (max_x.abs_diff(min_x) as u64 + 1, max_y.abs_diff(min_y) as u64 + 1);
+    if width.checked_mul(height).is_none_or(|points| points > Heatmap::MAX_POINTS) {
+        return sweep(input, diagonals);
+    }
+    let width = width as usize;
+    let mut grid = vec![0u8; width * height as usize];
+    let mut overlaps = 0;
+    for point in lines.iter().flat_map(|l| l.walk()) {
+        let cell = &mut grid[(point.y - min_y) as usize * width + (point.x - min_x) as usize];
+        if *cell == 1 {
+            overlaps += 1;
+        }
+        *cell = cell.saturating_add(1);
+    }
+    overlaps
+}
+
+fn vectors(input: &[Line], diagonals: bool) -> usize {
     let mut intersections = FxHashSet::default();
     for line in input {
         for other in input {
-            line.vector_intersection(other, true, &mut intersections);
+            line.vector_intersection(other, diagonals, &mut intersections);
         }
     }
     intersections.len()
 }
-*/
+
+#[aoc_generator(day5)]
+fn parse(input: &str) -> Result<Vec<Line>, ParseError> {
+    let regex = Regex::new(r#"^(\d+),(\d+) -> (\d+),(\d+)$"#).unwrap();
+    let input = Input::new(5, input);
+    input.lines().map(|l| {
+        let caps = regex.captures(l).ok_or_else(|| input.error(l, "expected `x1,y1 -> x2,y2`"))?;
+        let num = |i| input.parse(caps.get(i).unwrap().as_str());
+        Ok(((num(1)?, num(2)?), (num(3)?, num(4)?)).into())
+    }).collect()
+}
+
+#[aoc(day5, part1, vectors)]
+fn part1_vectors(input: &[Line]) -> usize {
+    overlaps(input, Strategy::Vectors, false)
+}
+
+#[aoc(day5, part1, points)]
+fn part1_points(input: &[Line]) -> usize {
+    overlaps(input, Strategy::Points, false)
+}
+
+#[aoc(day5, part1, dense)]
+fn part1_dense(input: &[Line]) -> usize {
+    overlaps(input, Strategy::Dense, false)
+}
+
+#[aoc(day5, part2, points)]
+fn part2_points(input: &[Line]) -> usize {
+    overlaps(input, Strategy::Points, true)
+}
+
+#[aoc(day5, part2, vectors)]
+fn part2_vectors(input: &[Line]) -> usize {
+    overlaps(input, Strategy::Vectors, true)
+}
+
+#[aoc(day5, part2, dense)]
+fn part2_dense(input: &[Line]) -> usize {
+    overlaps(input, Strategy::Dense, true)
+}
+
+/// Counts the overlaps with an exact sweep rather than visiting each point, for coordinates too large to rasterize
+fn sweep(input: &[Line], diagonals: bool) -> usize {
+    let segments: Vec<_> = input
+        .iter()
+        .filter(|l| diagonals || l.dx == 0 || l.dy == 0)
+        .map(|l| Segment::new((l.start.x, l.start.y), (l.end.x, l.end.y)))
+        .collect();
+    segments::intersections(&segments).lattice_points() as usize
+}
+
+#[aoc(day5, part1, sweep)]
+fn part1_sweep(input: &[Line]) -> usize {
+    overlaps(input, Strategy::Sweep, false)
+}
+
+#[aoc(day5, part2, sweep)]
+fn part2_sweep(input: &[Line]) -> usize {
+    overlaps(input, Strategy::Sweep, true)
+}
+
+pub(super) fn solutions() -> Vec<Box<dyn Solver>> {
+    vec![
+        Solution::boxed(5, 1, Some(Strategy::Points.name()), parse, part1_points),
+        Solution::boxed(5, 2, Some(Strategy::Points.name()), parse, part2_points),
+        Solution::boxed(5, 1, Some(Strategy::Vectors.name()), parse, part1_vectors),
+        Solution::boxed(5, 2, Some(Strategy::Vectors.name()), parse, part2_vectors),
+        Solution::boxed(5, 1, Some(Strategy::Sweep.name()), parse, part1_sweep),
+        Solution::boxed(5, 2, Some(Strategy::Sweep.name()), parse, part2_sweep),
+        Solution::boxed(5, 1, Some(Strategy::Dense.name()), parse, part1_dense),
+        Solution::boxed(5, 2, Some(Strategy::Dense.name()), parse, part2_dense),
+    ]
+}
 
 #[cfg(test)]
 mod tests {
@@ -191,8 +432,10 @@ mod tests {
 3,4 -> 1,4
 0,0 -> 8,8
 5,5 -> 8,2"#;
-        assert_eq!(part1_vectors(&parse(input)), 5);
-        assert_eq!(part1_points(&parse(input)), 5);
+        assert_eq!(part1_vectors(&parse(input).unwrap()), 5);
+        assert_eq!(part1_points(&parse(input).unwrap()), 5);
+        assert_eq!(part1_sweep(&parse(input).unwrap()), 5);
+        assert_eq!(part1_dense(&parse(input).unwrap()), 5);
     }
 
     #[test]
@@ -207,7 +450,87 @@ mod tests {
 3,4 -> 1,4
 0,0 -> 8,8
 5,5 -> 8,2"#;
-        assert_eq!(part2_vectors(&parse(input)), 12);
-        assert_eq!(part2_points(&parse(input)), 12);
+        assert_eq!(part2_vectors(&parse(input).unwrap()), 12);
+        assert_eq!(part2_points(&parse(input).unwrap()), 12);
+        assert_eq!(part2_sweep(&parse(input).unwrap()), 12);
+        assert_eq!(part2_dense(&parse(input).unwrap()), 12);
+    }
+
+    #[test]
+    fn strategies_agree() {
+        for seed in 0..5 {
+            let vents = Vents::parse(&crate::gen::generate(5, 40, seed).unwrap()).unwrap();
+            for diagonals in [false, true] {
+                let expected = vents.overlaps(Strategy::Points, diagonals);
+                for strategy in Strategy::ALL {
+                    assert_eq!(vents.overlaps(strategy, diagonals), expected, "{:?} on seed {}", strategy, seed);
+                }
+            }
+        }
+        // Too large a bounding box for the dense grid, it sweeps instead
+        let vents = Vents::parse("0,0 -> 5,0\n0,2000000000 -> 5,2000000000\n3,0 -> 3,0").unwrap();
+        assert!(Strategy::ALL.iter().all(|&s| vents.overlaps(s, false) == 1));
+        let long = Vents::parse("0,0 -> 2000000000,0\n0,2000000000 -> 5,2000000000\n7,0 -> 7,2000000000").unwrap();
+        assert_eq!(long.overlaps(Strategy::Dense, false), 1);
+        let vents = Vents::parse("5,5 -> 5,5\n5,5 -> 5,5\n900,3 -> 900,3").unwrap();
+        assert!(Strategy::ALL.iter().all(|&s| vents.overlaps(s, false) == 1));
+        assert!(Strategy::ALL.iter().all(|&s| Vents::parse("").unwrap().overlaps(s, true) == 0));
+    }
+
+    #[test]
+    fn any_slope() {
+        // 0,0 -> 9,3 only covers every third column, 3,1 and 6,2 between its ends, and 2,0 -> 8,1 only its ends
+        let vents = Vents::parse("0,0 -> 9,3\n3,0 -> 3,5\n6,0 -> 6,2\n0,2 -> 8,2\n2,0 -> 8,1\n2,0 -> 2,0\n9,3 -> 8,1")
+            .unwrap();
+        for strategy in Strategy::ALL {
+            assert_eq!(vents.overlaps(strategy, true), 6, "{:?}", strategy);
+            assert_eq!(vents.overlaps(strategy, false), 2, "{:?}", strategy);
+        }
+        let vents = Vents::parse("0,0 -> 3,1\n3,1 -> 0,0\n1,0 -> 1,1").unwrap();
+        assert!(Strategy::ALL.iter().all(|&s| vents.overlaps(s, true) == 2));
+    }
+
+    #[test]
+    fn heatmaps() {
+        let input = "0,9 -> 5,9\n8,0 -> 0,8\n9,4 -> 3,4\n2,2 -> 2,1\n7,0 -> 7,4\n6,4 -> 2,0\n0,9 -> 2,9\n3,4 -> 1,4\n\
+                     0,0 -> 8,8\n5,5 -> 8,2";
+        let vents = Vents::parse(input).unwrap();
+        let heatmap = vents.heatmap(true, None).unwrap();
+        assert_eq!((heatmap.origin, heatmap.counts.width(), heatmap.counts.height()), ((0, 0), 10, 10));
+        assert_eq!(heatmap.to_csv().lines().nth(4), Some("0,1,1,2,3,1,3,2,1,1"));
+        assert_eq!(heatmap.counts.cells().iter().filter(|&&c| c > 1).count(), 12);
+
+        let crop = vents.heatmap(true, Some("5,5,3,4".parse().unwrap())).unwrap();
+        assert_eq!((crop.origin, crop.to_csv().as_str()), ((3, 4), "2,3,1\n1,0,2\n"));
+        assert_eq!(crop.to_pgm(), b"P5\n3 2\n255\n\xaa\xff\x55\x55\x00\xaa");
+        assert_eq!(vents.heatmap(false, None).unwrap().max(), 2);
+        assert!("1,2,3".parse::<Region>().is_err());
+
+        let steep = Vents::parse("0,0 -> 3,1\n2,0 -> 2,0").unwrap().heatmap(true, None).unwrap();
+        assert_eq!(steep.to_csv(), "1,0,1,0\n0,0,0,1\n");
+        // Only the points inside the region are visited
+        let long = Vents::parse("0,0 -> 2000000000,2000000000\n2000000000,0 -> 2000000000,0").unwrap();
+        let crop = long.heatmap(true, Some("1999999999,1999999998,2000000000,2000000000".parse().unwrap())).unwrap();
+        assert_eq!(crop.to_csv(), "0,0\n1,0\n0,1\n");
+        assert_eq!(long.heatmap(false, None).unwrap().to_csv(), "1\n");
+        assert_eq!(long.heatmap(true, None), Err(RegionTooLarge { width: 2000000001, height: 2000000001 }));
+        let wide = "-2147483648,0,2147483647,0".parse().unwrap();
+        assert_eq!(long.heatmap(true, Some(wide)), Err(RegionTooLarge { width: 1 << 32, height: 1 }));
+    }
+
+    #[test]
+    fn large_coordinates() {
+        let input = "0,0 -> 3000000,3000000\n0,3000000 -> 3000000,0\n1000000,1000000 -> 5000000,5000000";
+        assert_eq!(part1_sweep(&parse(input).unwrap()), 0);
+        // The overlap of the diagonals, the other one crosses it
+        assert_eq!(part2_sweep(&parse(input).unwrap()), 2000001);
+    }
+
+    #[test]
+    fn malformed_input() {
+        let err = parse("0,9 -> 5,9\n8,0 - 0,8").err().unwrap();
+        assert_eq!((err.line, err.column, err.token.as_str()), (2, 1, "8,0 - 0,8"));
+        let err = parse("0,9 -> 5,99999999999").err().unwrap();
+        assert_eq!((err.line, err.column, err.token.as_str()), (1, 10, "99999999999"));
     }
 }
@@ -1,45 +1,397 @@
+use std::collections::VecDeque;
+use std::fmt::{self, Display};
+
+use crate::bignum::BigUint;
+use crate::parse::{Input, ParseError};
+use crate::sim::Simulation;
+use super::{Solution, Solver};
+
+/// How a species reproduces. Every fish has a timer counting down a day at a time. A fish whose timer is 0 gives birth
+/// and starts over from `reset`, its young start from `newborn`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Lifecycle {
+    pub reset: usize,
+    pub newborn: usize,
+    /// Number of days fish live, taking the initial fish as born on day 0. They never die if `None`.
+    pub death_age: Option<usize>,
+    /// Chance that a fish whose timer ran out gives birth. Populations are expected values when it is below 1.
+    pub birth_probability: f64,
+}
+
+impl Lifecycle {
+    /// The puzzle's fish
+    pub const LANTERNFISH: Lifecycle = Lifecycle { reset: 6, newborn: 8, death_age: None, birth_probability: 1.0 };
+
+    /// Whether populations are whole numbers that only depend on the timers, as counted by [`School`]
+    pub fn is_exact(&self) -> bool {
+        self.death_age.is_none() && self.birth_probability == 1.0
+    }
+
+    /// Number of timer values, enough for the given fish
+    fn timers(&self, fish: &[u8]) -> usize {
+        fish.iter().map(|&fish| fish as usize).chain([self.reset, self.newborn]).max().unwrap_or_default() + 1
+    }
+}
+
+/// Number of fish for each timer value
+#[derive(Clone, Debug)]
+pub struct School {
+    lifecycle: Lifecycle,
+    timers: Vec<usize>,
+    shift: fn(&mut [usize]),
+    generation: usize,
+}
+
+impl School {
+    /// Lanternfish, from the puzzle input
+    pub fn parse(input: &str) -> Result<Self, ParseError> {
+        parse(input).map(|fish| School::new(&fish, Lifecycle::LANTERNFISH, shift_copy))
+    }
+
+    /// Only takes [exact](Lifecycle::is_exact) lifecycles, [`Population`] counts the others
+    pub fn parse_with(input: &str, lifecycle: Lifecycle) -> Result<Self, SchoolError> {
+        if !lifecycle.is_exact() {
+            return Err(SchoolError::NotExact(lifecycle));
+        }
+        Ok(School::new(&parse(input)?, lifecycle, shift_copy))
+    }
+
+    /// The lifecycle must be exact
+    fn new(fish: &[u8], lifecycle: Lifecycle, shift: fn(&mut [usize])) -> Self {
+        let mut timers = vec![0usize; lifecycle.timers(fish)];
+        for &fish in fish {
+            timers[fish as usize] += 1;
+        }
+        School { lifecycle, timers, shift, generation: 0 }
+    }
+
+    fn len(&self) -> usize {
+        self.timers.iter().sum()
+    }
+
+    /// The exact number of fish after `days` more days. It grows by about one digit every 26 days, so a million days
+    /// takes seconds, while [`School::after_modulo`] stays instant.
+    pub fn after(&self, days: u64) -> BigUint {
+        self.population(days, |n| BigUint::from(n as u128))
+    }
+
+    /// The number of fish after `days` more days, if it fits in a `u128`
+    pub fn after_u128(&self, days: u64) -> Option<u128> {
+        self.population(days, |n| Some(n as u128))
+    }
+
+    /// The number of fish after `days` more days, modulo `modulus`
+    pub fn after_modulo(&self, days: u64, modulus: u64) -> u64 {
+        assert!(modulus > 0, "the modulus must be positive");
+        self.population(days, |n| Modular { value: n % modulus, modulus }).value
+    }
+
+    /// Raises the matrix moving the timers forward a day to the power `days`, by squaring, then applies it to the
+    /// timers. Takes O(log days) products of square matrices, 9 by 9 for lanternfish, instead of simulating each day.
+    fn population<C: Count>(&self, days: u64, lift: impl Fn(u64) -> C) -> C {
+        let (zero, one) = (lift(0), lift(1));
+        let Lifecycle { reset, newborn, .. } = self.lifecycle;
+        let size = self.timers.len();
+        // Row `i` holds how many fish with each timer end up with timer `i` the next day
+        let mut day: Matrix<C> = (0..size)
+            .map(|i| {
+                let births = (i == reset) as u64 + (i == newborn) as u64;
+                (0..size).map(|j| if j == 0 { lift(births) } else if j == i + 1 { one.clone() } else { zero.clone() })
+                    .collect()
+            })
+            .collect();
+        let mut total: Matrix<C> =
+            (0..size).map(|i| (0..size).map(|j| if i == j { one.clone() } else { zero.clone() }).collect()).collect();
+        let mut days = days;
+        while days > 0 {
+            if days & 1 == 1 {
+                total = multiply(&total, &day);
+            }
+            days >>= 1;
+            if days > 0 {
+                day = multiply(&day, &day);
+            }
+        }
+        let timers: Vec<C> = self.timers.iter().map(|&n| lift(n as u64)).collect();
+        let fish = total.iter().flat_map(|row| row.iter().zip(&timers).map(|(m, n)| m.mul(n)));
+        fish.fold(zero.clone(), |sum, n| sum.add(&n))
+    }
+}
+
+/// Why [`School::parse_with`] failed
+#[derive(Clone, Debug, PartialEq)]
+pub enum SchoolError {
+    Parse(ParseError),
+    /// Fish of this lifecycle die or may not give birth
+    NotExact(Lifecycle),
+}
+
+impl From<ParseError> for SchoolError {
+    fn from(error: ParseError) -> Self {
+        SchoolError::Parse(error)
+    }
+}
+
+impl Display for SchoolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchoolError::Parse(error) => error.fmt(f),
+            SchoolError::NotExact(_) => write!(f, "a school can't count fish that die or may not give birth"),
+        }
+    }
+}
+
+impl std::error::Error for SchoolError {}
+
+type Matrix<C> = Vec<Vec<C>>;
+
+fn multiply<C: Count>(a: &Matrix<C>, b: &Matrix<C>) -> Matrix<C> {
+    let size = a.len();
+    (0..size)
+        .map(|i| {
+            (0..size).map(|j| (1..size).fold(a[i][0].mul(&b[0][j]), |sum, k| sum.add(&a[i][k].mul(&b[k][j])))).collect()
+        })
+        .collect()
+}
+
+/// What populations can be counted in
+trait Count: Clone {
+    fn add(&self, other: &Self) -> Self;
+    fn mul(&self, other: &Self) -> Self;
+}
+
+impl Count for BigUint {
+    fn add(&self, other: &Self) -> Self {
+        self + other
+    }
+
+    fn mul(&self, other: &Self) -> Self {
+        self * other
+    }
+}
+
+/// `None` once the count overflowed. The counts only grow, except when multiplied by zero.
+impl Count for Option<u128> {
+    fn add(&self, other: &Self) -> Self {
+        self.and_then(|a| a.checked_add((*other)?))
+    }
+
+    fn mul(&self, other: &Self) -> Self {
+        match (*self, *other) {
+            (Some(0), _) | (_, Some(0)) => Some(0),
+            (Some(a), Some(b)) => a.checked_mul(b),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Modular {
+    value: u64,
+    modulus: u64,
+}
+
+impl Count for Modular {
+    fn add(&self, other: &Self) -> Self {
+        Modular { value: ((self.value as u128 + other.value as u128) % self.modulus as u128) as u64, ..*self }
+    }
+
+    fn mul(&self, other: &Self) -> Self {
+        Modular { value: (self.value as u128 * other.value as u128 % self.modulus as u128) as u64, ..*self }
+    }
+}
+
+impl Simulation for School {
+    /// Number of fish born
+    type Step = usize;
+    type Snapshot = Vec<usize>;
+
+    fn step(&mut self) -> usize {
+        self.generation += 1;
+        let bearing = self.timers[0];
+        (self.shift)(&mut self.timers);
+        *self.timers.last_mut().unwrap() = 0;
+        self.timers[self.lifecycle.reset] += bearing;
+        self.timers[self.lifecycle.newborn] += bearing;
+        bearing
+    }
+
+    fn generation(&self) -> usize {
+        self.generation
+    }
+
+    fn snapshot(&self) -> Vec<usize> {
+        self.timers.clone()
+    }
+}
+
+/// Expected number of fish for each age and timer value, for any lifecycle
+#[derive(Clone, Debug)]
+pub struct Population {
+    lifecycle: Lifecycle,
+    /// Fish by timer value, for each age from the youngest. A single group holds all ages when fish never die.
+    cohorts: VecDeque<Vec<f64>>,
+    generation: usize,
+}
+
+impl Population {
+    pub fn parse(input: &str, lifecycle: Lifecycle) -> Result<Self, ParseError> {
+        parse(input).map(|fish| Population::new(&fish, lifecycle))
+    }
+
+    fn new(fish: &[u8], lifecycle: Lifecycle) -> Self {
+        let mut timers = vec![0.0; lifecycle.timers(fish)];
+        for &fish in fish {
+            timers[fish as usize] += 1.0;
+        }
+        let mut cohorts = VecDeque::from([timers]);
+        cohorts.truncate(lifecycle.death_age.unwrap_or(1));
+        Population { lifecycle, cohorts, generation: 0 }
+    }
+
+    pub fn len(&self) -> f64 {
+        self.cohorts.iter().flatten().sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0.0
+    }
+}
+
+impl Simulation for Population {
+    /// Expected number of fish born
+    type Step = f64;
+    /// Expected number of fish for each timer value
+    type Snapshot = Vec<f64>;
+
+    fn step(&mut self) -> f64 {
+        self.generation += 1;
+        let Lifecycle { reset, newborn, death_age, birth_probability } = self.lifecycle;
+        let mut born = 0.0;
+        for timers in &mut self.cohorts {
+            let bearing = timers[0];
+            timers.rotate_left(1);
+            *timers.last_mut().unwrap() = 0.0;
+            timers[reset] += bearing;
+            born += bearing * birth_probability;
+        }
+        let size = self.cohorts.front().map_or(0, Vec::len);
+        match death_age {
+            None => self.cohorts[0][newborn] += born,
+            Some(age) => {
+                let mut young = vec![0.0; size.max(newborn + 1)];
+                young[newborn] = born;
+                self.cohorts.push_front(young);
+                self.cohorts.truncate(age);
+            }
+        }
+        born
+    }
+
+    fn generation(&self) -> usize {
+        self.generation
+    }
+
+    fn snapshot(&self) -> Vec<f64> {
+        let size = self.cohorts.iter().map(Vec::len).max().unwrap_or_default();
+        let mut timers = vec![0.0; size];
+        for cohort in &self.cohorts {
+            timers.iter_mut().zip(cohort).for_each(|(sum, n)| *sum += n);
+        }
+        timers
+    }
+}
 
 #[aoc_generator(day6)]
-fn parse(input: &str) -> Vec<u8> {
-    input.split(',').map(|s| s.parse().unwrap()).collect()
+fn parse(input: &str) -> Result<Vec<u8>, ParseError> {
+    let input = Input::new(6, input);
+    input.parse_list(input.text(), ',')
 }
 
 #[aoc(day6, part1)]
 fn part1(input: &[u8]) -> usize {
-    calc(input, 80)
+    calc(input, 80, shift_copy)
+}
+
+#[aoc(day6, part1, rotate)]
+fn part1_rotate(input: &[u8]) -> usize {
+    calc(input, 80, shift_rotate)
+}
+
+/// Simulates every fish on its own, like the puzzle describes it. Only usable for part 1.
+#[aoc(day6, part1, naive)]
+fn part1_naive(input: &[u8]) -> usize {
+    let mut fish = input.to_vec();
+    for _ in 0..80 {
+        let mut born = 0;
+        for timer in &mut fish {
+            if *timer == 0 {
+                *timer = 6;
+                born += 1;
+            } else {
+                *timer -= 1;
+            }
+        }
+        fish.extend(std::iter::repeat_n(8, born));
+    }
+    fish.len()
+}
+
+#[aoc(day6, part1, matrix)]
+fn part1_matrix(input: &[u8]) -> u128 {
+    School::new(input, Lifecycle::LANTERNFISH, shift_copy).after_u128(80).expect("fits in a u128")
 }
 
 #[aoc(day6, part2)]
 fn part2(input: &[u8]) -> usize {
-    calc(input, 256)
+    calc(input, 256, shift_copy)
+}
+
+#[aoc(day6, part2, rotate)]
+fn part2_rotate(input: &[u8]) -> usize {
+    calc(input, 256, shift_rotate)
 }
 
 // Feels so good predicting that Part 2 will just have more iterations :upside_down:
-fn calc(input: &[u8], days: usize) -> usize {
-    let mut timers = [0usize; 9];
-    for &fish in input {
-        timers[fish as usize] += 1;
-    }
+fn calc(input: &[u8], days: usize, shift: fn(&mut [usize])) -> usize {
     // We don't have to track states and lose our mind exponentially, we just need to count
     // the fish for each timer
+    let mut school = School::new(input, Lifecycle::LANTERNFISH, shift);
     for _ in 0..days {
-        let bearing = timers[0];
+        school.step();
+    }
+    school.len()
+}
 
-        // Shifting the array
+/// Shifts the timers down by one - fast + unsafe method
+fn shift_copy(timers: &mut [usize]) {
+    // We need to use pointers to copy a slice onto itself.
+    // SAFETY: usize is Copy, both slices are valid and properly aligned, and ptr::copy is well-defined for
+    // overlapping slices.
+    let len = timers.len() - 1;
+    unsafe { std::ptr::copy(timers[1..].as_ptr(), timers[..len].as_mut_ptr(), len) }
+}
 
-        // Fast + unsafe method
-        // Because the array is unsized, we need to use pointers to operate on slices.
-        // SAFETY: usize is Copy, both slices are valid and properly aligned, and intrinsics::copy is well-defined for
-        // overlapping slices.
-        unsafe { std::intrinsics::copy(timers[1..9].as_ptr(), timers[0..8].as_mut_ptr(), 8) }
+/// Safe method
+fn shift_rotate(timers: &mut [usize]) {
+    timers.rotate_left(1);
+}
 
-        // Safe method
-        // timers.rotate_left(1);
+#[aoc(day6, part2, matrix)]
+fn part2_matrix(input: &[u8]) -> u128 {
+    School::new(input, Lifecycle::LANTERNFISH, shift_copy).after_u128(256).expect("fits in a u128")
+}
 
-        timers[6] += bearing;
-        timers[8] = bearing;
-    }
-    timers.iter().sum()
+pub(super) fn solutions() -> Vec<Box<dyn Solver>> {
+    vec![
+        Solution::boxed(6, 1, None, parse, part1),
+        Solution::boxed(6, 2, None, parse, part2),
+        Solution::boxed(6, 1, Some("rotate"), parse, part1_rotate),
+        Solution::boxed(6, 2, Some("rotate"), parse, part2_rotate),
+        Solution::boxed(6, 1, Some("naive"), parse, part1_naive),
+        Solution::boxed(6, 1, Some("matrix"), parse, part1_matrix),
+        Solution::boxed(6, 2, Some("matrix"), parse, part2_matrix),
+    ]
 }
 
 #[cfg(test)]
@@ -49,12 +401,74 @@ mod tests {
     #[test]
     fn part1_example() {
         let input = r#"3,4,3,1,2"#;
-        assert_eq!(part1(&parse(input)), 5934);
+        assert_eq!(part1(&parse(input).unwrap()), 5934);
+        assert_eq!(part1_rotate(&parse(input).unwrap()), 5934);
+        assert_eq!(part1_naive(&parse(input).unwrap()), 5934);
+        assert_eq!(part1_matrix(&parse(input).unwrap()), 5934);
     }
 
     #[test]
     fn part2_example() {
         let input = r#"3,4,3,1,2"#;
-        assert_eq!(part2(&parse(input)), 26984457539);
+        assert_eq!(part2(&parse(input).unwrap()), 26984457539);
+        assert_eq!(part2_rotate(&parse(input).unwrap()), 26984457539);
+        assert_eq!(part2_matrix(&parse(input).unwrap()), 26984457539);
+    }
+
+    #[test]
+    fn many_days() {
+        let school = School::parse("3,4,3,1,2").unwrap();
+        for days in [0, 1, 18, 100] {
+            assert_eq!(school.after(days).to_string(), calc(&[3, 4, 3, 1, 2], days as usize, shift_copy).to_string());
+        }
+        // The last day that fits in a u128, and its count matches the exact one
+        let last = (256..).find(|&days| school.after_u128(days + 1).is_none()).unwrap();
+        assert_eq!(school.after_u128(last).unwrap().to_string(), school.after(last).to_string());
+        assert!(school.after(last + 1).to_string().parse::<u128>().is_err());
+
+        let exact = school.after(10_000).to_string();
+        let last_digits = exact[exact.len() - 9..].trim_start_matches('0');
+        assert_eq!(school.after_modulo(10_000, 1_000_000_000).to_string(), last_digits);
+        assert_eq!(school.after_modulo(10_000_000, 1), 0);
+        let mut stepped = school.clone();
+        for _ in 0..30 {
+            stepped.step();
+        }
+        assert_eq!(stepped.after_modulo(9_999_970, 1_000_000_007), school.after_modulo(10_000_000, 1_000_000_007));
+    }
+
+    #[test]
+    fn lifecycles() {
+        let run = |mut population: Population, days| {
+            for _ in 0..days {
+                population.step();
+            }
+            population.len()
+        };
+        let lanternfish = Population::parse("3,4,3,1,2", Lifecycle::LANTERNFISH).unwrap();
+        assert_eq!(run(lanternfish, 256), 26984457539.0);
+
+        // Resets to 2, young start at 3: after a week, the parent and its first young just gave birth again
+        let quick = Lifecycle { reset: 2, newborn: 3, ..Lifecycle::LANTERNFISH };
+        let mut school = School::parse_with("0", quick).unwrap();
+        assert_eq!(school.after_u128(4), Some(3));
+        for _ in 0..7 {
+            school.step();
+        }
+        assert_eq!(school.snapshot(), [2, 1, 1, 1]);
+        assert_eq!(school.after_u128(43), Some(run(Population::parse("0", quick).unwrap(), 50) as u128));
+
+        // Dies at 5 days old, after giving birth on day 4
+        let mortal = Lifecycle { death_age: Some(5), ..Lifecycle::LANTERNFISH };
+        assert_eq!(School::parse_with("3", mortal).err(), Some(SchoolError::NotExact(mortal)));
+        assert!(matches!(School::parse_with("3,x", quick), Err(SchoolError::Parse(e)) if e.token == "x"));
+        assert_eq!(run(Population::parse("3", mortal).unwrap(), 4), 2.0);
+        assert_eq!(run(Population::parse("3", mortal).unwrap(), 5), 1.0);
+        assert_eq!(run(Population::parse("3", Lifecycle { death_age: Some(0), ..mortal }).unwrap(), 3), 0.0);
+
+        let unlikely = Lifecycle { birth_probability: 0.5, ..Lifecycle::LANTERNFISH };
+        assert!(School::parse_with("0", unlikely).is_err());
+        assert_eq!(run(Population::parse("0", unlikely).unwrap(), 1), 1.5);
+        assert_eq!(run(Population::parse("0", unlikely).unwrap(), 8), 2.0);
     }
 }
@@ -1,47 +1,188 @@
 use itertools::Itertools;
+use crate::parse::{Input, ParseError};
+use super::{Solution, Solver};
 
-struct Crabs {
-    numbers: Vec<i32>,
-    max: i32
+/// Horizontal positions of the crabs, sorted
+#[derive(Clone, Debug)]
+pub struct Crabs {
+    numbers: Vec<i64>,
 }
 
-#[aoc_generator(day7)]
-fn parse(input: &str) -> Crabs {
-    let numbers: Vec<_> = input.split(',').map(|s| s.parse().unwrap()).sorted().collect();
-    let max = numbers.iter().max().copied().unwrap();
-    Crabs {
-        numbers,
-        max
+impl Crabs {
+    pub fn parse(input: &str) -> Result<Self, ParseError> {
+        parse(input)
+    }
+
+    /// The position costing the crabs the least fuel to move to, in O(n) after parsing for the linear and triangular
+    /// costs and O(n log range) for the others
+    pub fn align(&self, cost: &Cost) -> Alignment {
+        match cost {
+            Cost::Linear => self.evaluate(cost, self.numbers[(self.numbers.len() - 1) / 2]),
+            Cost::Triangular => {
+                // The best position is within 1/2 of the mean
+                let sum: i128 = self.numbers.iter().map(|&n| n as i128).sum();
+                let mean = sum.div_euclid(self.numbers.len() as i128) as i64;
+                (mean - 1..=mean + 2).map(|position| self.evaluate(cost, position)).min_by_key(|a| a.fuel).unwrap()
+            }
+            Cost::Convex(_) => self.ternary_search(cost),
+        }
+    }
+
+    /// Narrows down the range between the first and last crabs, the total fuel is convex too
+    fn ternary_search(&self, cost: &Cost) -> Alignment {
+        let (mut low, mut high) = (self.numbers[0], self.numbers[self.numbers.len() - 1]);
+        while high - low > 2 {
+            let third = (high - low) / 3;
+            let (left, right) = (self.evaluate(cost, low + third), self.evaluate(cost, high - third));
+            match left.fuel.cmp(&right.fuel) {
+                std::cmp::Ordering::Less => high = right.position - 1,
+                std::cmp::Ordering::Greater => low = left.position + 1,
+                // Both sides of the minimum, or on a flat bottom
+                std::cmp::Ordering::Equal => (low, high) = (left.position, right.position),
+            }
+        }
+        (low..=high).map(|position| self.evaluate(cost, position)).min_by_key(|a| a.fuel).unwrap()
+    }
+
+    fn evaluate(&self, cost: &Cost, position: i64) -> Alignment {
+        let fuel = self.numbers.iter().map(|&n| cost.fuel(n.abs_diff(position))).sum();
+        Alignment { position, fuel }
+    }
+}
+
+/// Fuel a crab burns to move some distance
+pub enum Cost {
+    /// One unit per step
+    Linear,
+    /// One more unit for each step than for the previous one
+    Triangular,
+    /// Fuel for a distance. It must never decrease and its increments must never shrink, or the best position found
+    /// might not be the best one.
+    Convex(Box<dyn Fn(u64) -> u128 + Send + Sync>),
+}
+
+impl Cost {
+    pub fn fuel(&self, distance: u64) -> u128 {
+        match self {
+            Cost::Linear => distance as u128,
+            // https://en.wikipedia.org/wiki/Triangular_number
+            Cost::Triangular => distance as u128 * (distance as u128 + 1) / 2,
+            Cost::Convex(fuel) => fuel(distance),
+        }
     }
 }
 
+/// Where the crabs line up, among the best positions, and the fuel they burn getting there
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Alignment {
+    pub position: i64,
+    pub fuel: u128,
+}
+
+#[aoc_generator(day7)]
+fn parse(input: &str) -> Result<Crabs, ParseError> {
+    let input = Input::new(7, input);
+    // Never empty, an empty input fails to parse as a number
+    let numbers = input.parse_list(input.text(), ',')?.into_iter().sorted().collect();
+    Ok(Crabs { numbers })
+}
+
 #[aoc(day7, part1)]
-fn part1(input: &Crabs) -> i32 {
-    (0..=input.max).map(|i| input.numbers.iter().map(|&n| (n - i).abs()).sum()).min().unwrap()
+fn part1(input: &Crabs) -> u128 {
+    input.align(&Cost::Linear).fuel
+}
+
+#[aoc(day7, part1, naive)]
+fn part1_naive(input: &Crabs) -> u128 {
+    naive(input, &Cost::Linear)
+}
+
+#[aoc(day7, part1, convex)]
+fn part1_convex(input: &Crabs) -> u128 {
+    input.align(&Cost::Convex(Box::new(|distance| distance as u128))).fuel
 }
 
 #[aoc(day7, part2)]
-fn part2(input: &Crabs) -> i32 {
-    (0..=input.max).map(|i| input.numbers.iter().map(|&n| {
-        // https://en.wikipedia.org/wiki/Triangular_number
-        let n = (n - i).abs();
-        (n * n + n) / 2
-    }).sum()).min().unwrap()
+fn part2(input: &Crabs) -> u128 {
+    input.align(&Cost::Triangular).fuel
+}
+
+#[aoc(day7, part2, naive)]
+fn part2_naive(input: &Crabs) -> u128 {
+    naive(input, &Cost::Triangular)
+}
+
+#[aoc(day7, part2, convex)]
+fn part2_convex(input: &Crabs) -> u128 {
+    let triangular = |distance: u64| distance as u128 * (distance as u128 + 1) / 2;
+    input.align(&Cost::Convex(Box::new(triangular))).fuel
+}
+
+/// Tries every position against every crab
+fn naive(input: &Crabs, cost: &Cost) -> u128 {
+    let (first, last) = (input.numbers[0], input.numbers[input.numbers.len() - 1]);
+    (first..=last).map(|i| input.evaluate(cost, i).fuel).min().unwrap()
+}
+
+pub(super) fn solutions() -> Vec<Box<dyn Solver>> {
+    vec![
+        Solution::boxed(7, 1, None, parse, part1),
+        Solution::boxed(7, 2, None, parse, part2),
+        Solution::boxed(7, 1, Some("naive"), parse, part1_naive),
+        Solution::boxed(7, 2, Some("naive"), parse, part2_naive),
+        Solution::boxed(7, 1, Some("convex"), parse, part1_convex),
+        Solution::boxed(7, 2, Some("convex"), parse, part2_convex),
+    ]
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
 
     #[test]
     fn part1_example() {
         let input = r#"16,1,2,0,4,2,7,1,2,14"#;
-        assert_eq!(part1(&parse(input)), 37);
+        assert_eq!(part1(&parse(input).unwrap()), 37);
+        assert_eq!(part1_naive(&parse(input).unwrap()), 37);
+        assert_eq!(part1_convex(&parse(input).unwrap()), 37);
+        assert_eq!(parse(input).unwrap().align(&Cost::Linear), Alignment { position: 2, fuel: 37 });
     }
 
     #[test]
     fn part2_example() {
         let input = r#"16,1,2,0,4,2,7,1,2,14"#;
-        assert_eq!(part2(&parse(input)), 168);
+        assert_eq!(part2(&parse(input).unwrap()), 168);
+        assert_eq!(part2_naive(&parse(input).unwrap()), 168);
+        assert_eq!(part2_convex(&parse(input).unwrap()), 168);
+        assert_eq!(parse(input).unwrap().align(&Cost::Triangular), Alignment { position: 5, fuel: 168 });
+    }
+
+    #[test]
+    fn alignments_match_naive() {
+        let mut rng = ChaCha8Rng::seed_from_u64(7);
+        let square = Cost::Convex(Box::new(|distance| distance as u128 * distance as u128));
+        let flat_then_linear = Cost::Convex(Box::new(|distance| distance.saturating_sub(5) as u128));
+        for _ in 0..200 {
+            let len = rng.gen_range(1..20);
+            let crabs = Crabs { numbers: (0..len).map(|_| rng.gen_range(-30..30)).sorted().collect() };
+            for cost in [&Cost::Linear, &Cost::Triangular, &square, &flat_then_linear] {
+                let alignment = crabs.align(cost);
+                assert_eq!(alignment.fuel, naive(&crabs, cost), "{:?}", crabs.numbers);
+                assert_eq!(crabs.evaluate(cost, alignment.position), alignment);
+            }
+        }
+    }
+
+    #[test]
+    fn far_apart() {
+        let crabs = Crabs::parse("0,1000000000,1000000000").unwrap();
+        assert_eq!(crabs.align(&Cost::Linear), Alignment { position: 1_000_000_000, fuel: 1_000_000_000 });
+        let triangular = crabs.align(&Cost::Triangular);
+        assert_eq!(triangular.position, 666_666_667);
+        assert_eq!(triangular.fuel, Cost::Triangular.fuel(666_666_667) + 2 * Cost::Triangular.fuel(333_333_333));
+        let cubic = Cost::Convex(Box::new(|distance| (distance as u128).pow(3)));
+        assert_eq!(crabs.align(&cubic).position, 585_786_438);
     }
 }
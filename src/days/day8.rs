@@ -1,5 +1,7 @@
 use fxhash::{FxHashMap, FxHashSet};
 use itertools::Itertools;
+use crate::parse::{Input, ParseError};
+use super::{Solution, Solver};
 
 struct SegmentDisplay {
     patterns: Vec<FxHashSet<char>>,
@@ -49,15 +51,25 @@ impl Parser {
 }
 
 #[aoc_generator(day8)]
-fn parse(input: &str) -> Vec<SegmentDisplay> {
+fn parse(input: &str) -> Result<Vec<SegmentDisplay>, ParseError> {
+    let input = Input::new(8, input);
+    let segments = |s: &str, count: usize| {
+        let digits = s.split_whitespace()
+            .map(|s| input.chars(s, "abcdefg").map(|c| c.into_iter().sorted()))
+            .collect::<Result<Vec<_>, _>>()?;
+        if digits.len() != count {
+            return Err(input.error(s, format!("expected {} digits", count)));
+        }
+        Ok(digits)
+    };
     input.lines().map(|l| {
-        let (patterns, output) = l.split(" | ").collect_tuple().unwrap();
-        let patterns: Vec<_> = patterns.split_whitespace().map(|s| s.chars().sorted().collect()).collect();
-        let output: Vec<_> = output.split_whitespace().map(|s| s.chars().sorted().collect()).collect();
-        SegmentDisplay {
+        let (patterns, output) = input.split_once(l, " | ")?;
+        let patterns = segments(patterns, 10)?.into_iter().map(|s| s.collect()).collect();
+        let output = segments(output, 4)?.into_iter().map(|s| s.collect()).collect();
+        Ok(SegmentDisplay {
             patterns,
             output,
-        }
+        })
     }).collect()
 }
 
@@ -124,6 +136,13 @@ fn diff(s1: &FxHashSet<char>, s2: &FxHashSet<char>) -> FxHashSet<char> {
     s1.difference(s2).copied().collect()
 }
 
+pub(super) fn solutions() -> Vec<Box<dyn Solver>> {
+    vec![
+        Solution::boxed(8, 1, None, parse, part1),
+        Solution::boxed(8, 2, None, parse, part2),
+    ]
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -140,7 +159,7 @@ dbcfg fgd bdegcaf fgec aegbdf ecdfab fbedc dacgb gdcebf gf | cefg dcbef fcge gbc
 bdfegc cbegaf gecbf dfcage bdacg ed bedf ced adcbefg gebcd | ed bcgafe cdgba cbgef
 egadfb cdbfeg cegd fecab cgb gbdefca cg fgcdab egfdb bfceg | gbdfcae bgc cg cgb
 gcafb gcf dcaebfg ecagb gf abcdeg gaef cafbge fdbac fegbdc | fgae cfgab fg bagce"#;
-        assert_eq!(part1(&parse(input)), 26);
+        assert_eq!(part1(&parse(input).unwrap()), 26);
     }
 
     #[test]
@@ -155,6 +174,6 @@ dbcfg fgd bdegcaf fgec aegbdf ecdfab fbedc dacgb gdcebf gf | cefg dcbef fcge gbc
 bdfegc cbegaf gecbf dfcage bdacg ed bedf ced adcbefg gebcd | ed bcgafe cdgba cbgef
 egadfb cdbfeg cegd fecab cgb gbdefca cg fgcdab egfdb bfceg | gbdfcae bgc cg cgb
 gcafb gcf dcaebfg ecagb gf abcdeg gaef cafbge fdbac fegbdc | fgae cfgab fg bagce"#;
-        assert_eq!(part2(&parse(input)), 61229);
+        assert_eq!(part2(&parse(input).unwrap()), 61229);
     }
 }
@@ -1,103 +1,54 @@
 use fxhash::FxHashSet;
 use itertools::Itertools;
+use crate::grid::{Grid, Neighbours};
+use crate::parse::{Input, ParseError};
+use super::{Solution, Solver};
 
-struct Grid {
-    inner: Vec<Vec<char>>,
-    line_len: isize,
-}
-
-struct GridIter<'g> {
-    pos: (isize, isize),
-    grid: &'g Grid,
-}
-
-impl Grid {
-    pub fn from(raw: Vec<Vec<char>>) -> Self {
-        Grid {
-            line_len: raw[0].len() as isize,
-            inner: raw,
-        }
-    }
-
-    pub fn iter_windows(&self, start_pos: (isize, isize)) -> GridIter<'_> {
-        GridIter {
-            pos: start_pos,
-            grid: self,
-        }
-    }
-}
-
-impl<'g> Iterator for GridIter<'g> {
-    type Item = [Option<((isize, isize), &'g char)>; 5];
-
-    fn next(&mut self) -> Option<Self::Item> {
-        let mut window = [None; 5];
-
-        let mut i = 0;
-        for x in -1..=1 {
-            for z in -1..=1 {
-                if x == 0 || z == 0 {
-                    window[i] = self.grid.inner.get((self.pos.1 + z) as usize)
-                        .and_then(|v| v.get((self.pos.0 + x) as usize))
-                        .map(|c| ((self.pos.0 + x, self.pos.1 + z), c));
-                    i += 1;
-                }
-            }
-        }
-        self.pos.0 += 1;
-        if self.pos.0 >= self.grid.line_len {
-            self.pos.0 = 0;
-            self.pos.1 += 1;
-        }
-        window[2].map(|_| window)
-    }
+#[aoc_generator(day9)]
+fn parse(input: &str) -> Result<Grid<u8>, ParseError> {
+    let input = Input::new(9, input);
+    Grid::parse(&input, input.text(), "0123456789", |c| c.to_digit(10).unwrap() as u8)
 }
 
-#[aoc_generator(day9)]
-fn parse(input: &str) -> Grid {
-    Grid::from(input.lines().map(|s| s.chars().collect()).collect())
+fn low_points(grid: &Grid<u8>) -> impl Iterator<Item = ((usize, usize), u8)> + '_ {
+    grid.iter()
+        .filter(|&(pos, &mid)| grid.neighbours(pos, Neighbours::Four).all(|n| grid[n] > mid))
+        .map(|(pos, &mid)| (pos, mid))
 }
 
 #[aoc(day9, part1)]
-fn part1(input: &Grid) -> u32 {
-    input.iter_windows((0, 0))
-        .filter_map(|w| {
-            let mid = *w[2].unwrap().1;
-            w[0..2].iter().chain(w[3..5].iter()).flatten().all(|(_, &c)| c > mid)
-                .then(|| 1 + mid.to_digit(10).unwrap())
-        })
-        .sum()
+fn part1(input: &Grid<u8>) -> u32 {
+    low_points(input).map(|(_, mid)| 1 + mid as u32).sum()
 }
 
 #[aoc(day9, part2)]
-fn part2(input: &Grid) -> usize {
-    let low_points = input.iter_windows((0, 0))
-        .filter_map(|w| {
-            let (mid_point, &mid) = w[2].unwrap();
-            w[0..2].iter().chain(w[3..5].iter()).flatten().all(|(_, &c)| c > mid)
-                .then(|| (mid_point, mid))
-        });
-    let sizes = low_points
-        .map(|((x, z), point)| {
+fn part2(input: &Grid<u8>) -> usize {
+    let sizes = low_points(input)
+        .map(|(pos, _)| {
             let mut points = FxHashSet::default();
-            spread_basin(input, (x, z), point, &mut points);
-            points.len() + 1
+            points.insert(pos);
+            spread_basin(input, pos, &mut points);
+            points.len()
         });
     sizes.sorted().rev().take(3).product()
 }
 
-fn spread_basin(grid: &Grid, start_pos: (isize, isize), value: char, points: &mut FxHashSet<(isize, isize)>) {
-    for ((x, z), new_value) in grid.iter_windows(start_pos).next().into_iter()
-        .flat_map(|a| a.into_iter().flatten())
-        .filter(|(pos, &v)| v != '9' && *pos != start_pos && (value as u8) <= v as u8) {
-        let pos = (x, z);
-        if !points.contains(&pos) {
-            points.insert(pos);
-            spread_basin(grid, pos, *new_value, points);
+fn spread_basin(grid: &Grid<u8>, start_pos: (usize, usize), points: &mut FxHashSet<(usize, usize)>) {
+    let value = grid[start_pos];
+    for pos in grid.neighbours(start_pos, Neighbours::Four).filter(|&n| grid[n] != 9 && value <= grid[n]) {
+        if points.insert(pos) {
+            spread_basin(grid, pos, points);
         }
     }
 }
 
+pub(super) fn solutions() -> Vec<Box<dyn Solver>> {
+    vec![
+        Solution::boxed(9, 1, None, parse, part1),
+        Solution::boxed(9, 2, None, parse, part2),
+    ]
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -109,7 +60,7 @@ mod tests {
 9856789892
 8767896789
 9899965678"#;
-        assert_eq!(part1(&parse(input)), 15);
+        assert_eq!(part1(&parse(input).unwrap()), 15);
     }
 
     #[test]
@@ -119,6 +70,6 @@ mod tests {
 9856789892
 8767896789
 9899965678"#;
-        assert_eq!(part2(&parse(input)), 1134);
+        assert_eq!(part2(&parse(input).unwrap()), 1134);
     }
 }
@@ -1,3 +1,9 @@
+use std::borrow::Borrow;
+use std::fmt::Display;
+use std::time::{Duration, Instant};
+
+use crate::parse::ParseError;
+
 mod day1;
 mod day2;
 mod day3;
@@ -23,3 +29,173 @@ mod day22;
 mod day23;
 mod day24;
 mod day25;
+
+pub use day1::{stream_increases, DepthReport, Jump, Run, SlidingWindow};
+pub use day2::{Aimed, Bounded, Drift, Instruction, Models, MovementModel, RunError, Script, Simple};
+pub use day3::{ColumnCounts, DiagnosticReport, Rating, RatingError, RatingStep, Selection};
+pub use day4::{Bingo, Event, Events, GameState, Rules, Win, WinRule};
+pub use day5::{Heatmap, Region, RegionTooLarge, Strategy, Vents};
+pub use day11::Octopuses;
+pub use day13::Paper;
+pub use day15::Cavern;
+pub use day20::Image;
+pub use day25::{Cucumber, Herds};
+pub use day6::{Lifecycle, Population, School, SchoolError};
+pub use day7::{Alignment, Cost, Crabs};
+
+/// A solution for one part of a day, runnable on raw puzzle input without going through `cargo aoc`.
+pub trait Solver: Sync {
+    fn day(&self) -> u8;
+
+    fn part(&self) -> u8;
+
+    /// The variant name, as given to `#[aoc]` (e.g. `points` for day 5), if any
+    fn variant(&self) -> Option<&'static str>;
+
+    /// Parses the input with the day's generator and runs the solution on it
+    fn run(&self, input: &str) -> Result<String, ParseError>;
+
+    /// Same as [`Solver::run`], timing the generator and the solution separately
+    fn run_timed(&self, input: &str) -> Result<Timed, ParseError>;
+}
+
+pub struct Timed {
+    pub answer: String,
+    pub generator: Duration,
+    pub solver: Duration,
+}
+
+/// Pairs a generator with a solution function, mirroring what `#[aoc_generator]` and `#[aoc]` do.
+pub struct Solution<T, I: ?Sized, R> {
+    day: u8,
+    part: u8,
+    variant: Option<&'static str>,
+    generator: fn(&str) -> Result<T, ParseError>,
+    solver: fn(&I) -> R,
+}
+
+impl<T, I: ?Sized, R> Solution<T, I, R>
+where
+    T: Borrow<I> + 'static,
+    I: 'static,
+    R: Display + 'static,
+{
+    pub fn boxed(
+        day: u8,
+        part: u8,
+        variant: Option<&'static str>,
+        generator: fn(&str) -> Result<T, ParseError>,
+        solver: fn(&I) -> R,
+    ) -> Box<dyn Solver> {
+        Box::new(Solution {
+            day,
+            part,
+            variant,
+            generator,
+            solver,
+        })
+    }
+}
+
+impl<T, I: ?Sized, R> Solver for Solution<T, I, R>
+where
+    T: Borrow<I>,
+    R: Display,
+{
+    fn day(&self) -> u8 {
+        self.day
+    }
+
+    fn part(&self) -> u8 {
+        self.part
+    }
+
+    fn variant(&self) -> Option<&'static str> {
+        self.variant
+    }
+
+    fn run(&self, input: &str) -> Result<String, ParseError> {
+        let input = (self.generator)(input)?;
+        Ok((self.solver)(input.borrow()).to_string())
+    }
+
+    fn run_timed(&self, input: &str) -> Result<Timed, ParseError> {
+        let start = Instant::now();
+        let input = (self.generator)(input)?;
+        let generator = start.elapsed();
+
+        let start = Instant::now();
+        let answer = (self.solver)(input.borrow());
+        let solver = start.elapsed();
+        Ok(Timed {
+            answer: answer.to_string(),
+            generator,
+            solver,
+        })
+    }
+}
+
+type Registration = fn() -> Vec<Box<dyn Solver>>;
+
+/// Every registered solution, sorted by day and part. Variants keep their registration order, the first one being
+/// the default.
+pub fn registry() -> Vec<Box<dyn Solver>> {
+    let days: [Registration; 25] = [
+        day1::solutions,
+        day2::solutions,
+        day3::solutions,
+        day4::solutions,
+        day5::solutions,
+        day6::solutions,
+        day7::solutions,
+        day8::solutions,
+        day9::solutions,
+        day10::solutions,
+        day11::solutions,
+        day12::solutions,
+        day13::solutions,
+        day14::solutions,
+        day15::solutions,
+        day16::solutions,
+        day17::solutions,
+        day18::solutions,
+        day19::solutions,
+        day20::solutions,
+        day21::solutions,
+        day22::solutions,
+        day23::solutions,
+        day24::solutions,
+        day25::solutions,
+    ];
+    let mut solutions: Vec<_> = days.iter().flat_map(|f| f()).collect();
+    solutions.sort_by_key(|s| (s.day(), s.part()));
+    solutions
+}
+
+/// Finds the solution for the given day and part. Without a variant name, the default one is returned.
+pub fn find(day: u8, part: u8, variant: Option<&str>) -> Option<Box<dyn Solver>> {
+    registry().into_iter().find(|s| {
+        s.day() == day && s.part() == part && (variant.is_none() || s.variant() == variant)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registry_covers_every_day() {
+        let solvers = registry();
+        for day in 1..=25 {
+            assert!(solvers.iter().any(|s| s.day() == day && s.part() == 1));
+        }
+    }
+
+    #[test]
+    fn find_runs_generator_and_solver() {
+        let input = example!(199 200 208 210 200 207 240 269 260 263);
+        assert_eq!(find(1, 2, None).unwrap().run(input).unwrap(), "5");
+        assert_eq!(find(5, 1, Some("points")).unwrap().variant(), Some("points"));
+        assert!(find(5, 1, Some("missing")).is_none());
+    }
+}
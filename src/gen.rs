@@ -0,0 +1,686 @@
+//! Random puzzle inputs, for benchmarks, fuzzing and cross-checking variants against each other.
+//!
+//! Every generator follows the constraints of the real inputs that the solutions rely on (every bingo roll is on a
+//! board, snailfish numbers are reduced, MONAD has 14 blocks, ...). What `size` controls depends on the day:
+//!
+//! | Day | `size` | Day | `size` |
+//! |-----|--------|-----|--------|
+//! | 1 | depth measurements | 14 | template length |
+//! | 2 | commands | 15 | side of the cave |
+//! | 3 | diagnostic numbers | 16 | packets in the transmission |
+//! | 4 | side of the boards, at most 16 | 17 | distance to the target area |
+//! | 5 | vent lines | 18 | snailfish numbers |
+//! | 6 | lanternfish | 19 | scanners |
+//! | 7 | crabs | 20 | side of the image |
+//! | 8 | displays | 21 | ignored |
+//! | 9 | side of the heightmap | 22 | reboot steps |
+//! | 10 | lines | 23 | depth of the rooms |
+//! | 11 | side of the grid | 24 | ignored, MONAD always reads 14 digits |
+//! | 12 | caves | 25 | side of the sea floor |
+//! | 13 | dots | | |
+
+use std::collections::{BTreeSet, HashSet};
+
+use itertools::Itertools;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::grid::{Grid, Neighbours};
+
+/// Size giving inputs comparable to the real ones
+pub fn default_size(day: u8) -> usize {
+    match day {
+        1 => 2000,
+        2 | 3 | 7 => 1000,
+        4 => 5,
+        23 => 2,
+        5 => 500,
+        6 => 300,
+        8 | 17 => 200,
+        9 | 10 | 15 | 18 | 20 => 100,
+        11 => 10,
+        12 => 12,
+        13 => 800,
+        14 => 20,
+        16 => 60,
+        19 => 30,
+        22 => 420,
+        25 => 137,
+        _ => 0,
+    }
+}
+
+/// Generates an input for `day`, `None` for days outside 1 to 25. The same seed always gives the same input.
+pub fn generate(day: u8, size: usize, seed: u64) -> Option<String> {
+    let rng = &mut ChaCha8Rng::seed_from_u64(seed);
+    let size = size.max(1);
+    Some(match day {
+        1 => day1(rng, size),
+        2 => day2(rng, size),
+        3 => day3(rng, size),
+        4 => day4(rng, size),
+        5 => day5(rng, size),
+        6 => day6(rng, size),
+        7 => day7(rng, size),
+        8 => day8(rng, size),
+        9 => day9(rng, size),
+        10 => day10(rng, size),
+        11 => day11(rng, size),
+        12 => day12(rng, size),
+        13 => day13(rng, size),
+        14 => day14(rng, size),
+        15 => day15(rng, size),
+        16 => day16(rng, size),
+        17 => day17(rng, size),
+        18 => day18(rng, size),
+        19 => day19(rng, size),
+        20 => day20(rng, size),
+        21 => day21(rng),
+        22 => day22(rng, size),
+        23 => day23(rng, size),
+        24 => day24(rng),
+        25 => day25(rng, size),
+        _ => return None,
+    })
+}
+
+fn day1(rng: &mut ChaCha8Rng, size: usize) -> String {
+    let mut depth: i32 = rng.gen_range(100..200);
+    (0..size)
+        .map(|_| {
+            depth = (depth + rng.gen_range(-10..=30)).max(0);
+            depth
+        })
+        .join("\n")
+}
+
+fn day2(rng: &mut ChaCha8Rng, size: usize) -> String {
+    // Keeps the aim positive, like the real inputs, so the submarine never flies
+    let mut aim = 0;
+    (0..size)
+        .map(|_| {
+            let value = rng.gen_range(1..=9);
+            match rng.gen_range(0..3) {
+                0 => format!("forward {}", value),
+                1 if aim >= value => {
+                    aim -= value;
+                    format!("up {}", value)
+                }
+                _ => {
+                    aim += value;
+                    format!("down {}", value)
+                }
+            }
+        })
+        .join("\n")
+}
+
+fn day3(rng: &mut ChaCha8Rng, size: usize) -> String {
+    let width = (usize::BITS - size.leading_zeros() + 1).clamp(12, 31) as usize;
+    // Both ratings must narrow down to a single number, which doesn't happen when every remaining number has the same
+    // bit at some position
+    loop {
+        let numbers = rand::seq::index::sample(rng, 1 << width, size.min(1 << width)).into_vec();
+        if [true, false].iter().all(|&most_common| rating_found(&numbers, width, most_common)) {
+            return numbers.iter().map(|n| format!("{:0width$b}", n, width = width)).join("\n");
+        }
+    }
+}
+
+fn rating_found(numbers: &[usize], width: usize, most_common: bool) -> bool {
+    let mut numbers = numbers.to_vec();
+    for bit in (0..width).rev() {
+        if numbers.len() == 1 {
+            break;
+        }
+        let ones = numbers.iter().filter(|&&n| n >> bit & 1 == 1).count();
+        let keep = (2 * ones >= numbers.len()) == most_common;
+        numbers.retain(|&n| (n >> bit & 1 == 1) == keep);
+    }
+    numbers.len() == 1
+}
+
+fn day4(rng: &mut ChaCha8Rng, size: usize) -> String {
+    let side = size.clamp(2, 16);
+    let pool: Vec<u64> = (0..(side * side * 2).max(100) as u64).collect();
+    let boards: Vec<Vec<u64>> = (0..100).map(|_| pool.choose_multiple(rng, side * side).copied().collect()).collect();
+    // Every roll must be on a board, and rolling every number makes sure each board wins
+    let mut rolls: Vec<_> = boards.iter().flatten().copied().collect::<BTreeSet<_>>().into_iter().collect();
+    rolls.shuffle(rng);
+
+    let width = pool.len().to_string().len();
+    let boards = boards.iter().map(|board| {
+        board.chunks(side).map(|row| row.iter().map(|n| format!("{:>width$}", n, width = width)).join(" ")).join("\n")
+    });
+    std::iter::once(rolls.iter().join(",")).chain(boards).join("\n\n")
+}
+
+fn day5(rng: &mut ChaCha8Rng, size: usize) -> String {
+    (0..size)
+        .map(|_| {
+            let (x, y): (i32, i32) = (rng.gen_range(0..1000), rng.gen_range(0..1000));
+            let (dx, dy) = *[(1, 0), (0, 1), (1, 1), (1, -1), (-1, 0), (0, -1), (-1, -1), (-1, 1)].choose(rng).unwrap();
+            // Longest line in that direction staying on the map
+            let room = |p: i32, d: i32| match d {
+                1 => 999 - p,
+                -1 => p,
+                _ => i32::MAX,
+            };
+            let len = rng.gen_range(0..=room(x, dx).min(room(y, dy)).min(500));
+            format!("{},{} -> {},{}", x, y, x + dx * len, y + dy * len)
+        })
+        .join("\n")
+}
+
+fn day6(rng: &mut ChaCha8Rng, size: usize) -> String {
+    (0..size).map(|_| rng.gen_range(1..=5)).join(",")
+}
+
+fn day7(rng: &mut ChaCha8Rng, size: usize) -> String {
+    // Most crabs are close to the start, a few are far away
+    (0..size)
+        .map(|_| {
+            let r: u32 = rng.gen_range(0..2000);
+            r * r / 2000
+        })
+        .join(",")
+}
+
+fn day8(rng: &mut ChaCha8Rng, size: usize) -> String {
+    const DIGITS: [&str; 10] =
+        ["abcefg", "cf", "acdeg", "acdfg", "bcdf", "abdfg", "abdefg", "acf", "abcdefg", "abcdfg"];
+    (0..size)
+        .map(|_| {
+            let mut wiring: Vec<char> = "abcdefg".chars().collect();
+            wiring.shuffle(rng);
+            let output: Vec<usize> = (0..4).map(|_| rng.gen_range(0..10)).collect();
+            let mut patterns: Vec<usize> = (0..10).collect();
+            patterns.shuffle(rng);
+            let mut scramble = |digit: usize| {
+                let mut segments: Vec<char> =
+                    DIGITS[digit].chars().map(|c| wiring[(c as u8 - b'a') as usize]).collect();
+                segments.shuffle(rng);
+                segments.into_iter().collect::<String>()
+            };
+            let patterns = patterns.into_iter().map(&mut scramble).join(" ");
+            let output = output.into_iter().map(&mut scramble).join(" ");
+            format!("{} | {}", patterns, output)
+        })
+        .join("\n")
+}
+
+fn day9(rng: &mut ChaCha8Rng, size: usize) -> String {
+    // Below the percolation threshold, so basins stay bounded by 9s
+    (0..size)
+        .map(|_| {
+            (0..size)
+                .map(|_| if rng.gen_bool(0.45) { '9' } else { char::from(b'0' + rng.gen_range(0..9)) })
+                .collect::<String>()
+        })
+        .join("\n")
+}
+
+fn day10(rng: &mut ChaCha8Rng, size: usize) -> String {
+    const OPENERS: [char; 4] = ['(', '[', '{', '<'];
+    const CLOSERS: [char; 4] = [')', ']', '}', '>'];
+    (0..size)
+        .map(|i| {
+            // At least one line must be incomplete for part 2
+            let corrupted = i > 0 && rng.gen_bool(0.5);
+            let len = rng.gen_range(20..=110);
+            let corrupt_at = rng.gen_range(0..len);
+            let (mut line, mut stack) = (String::new(), vec![]);
+            for j in 0..len {
+                if corrupted && j == corrupt_at {
+                    let wrong = stack.last().map_or(0, |&top| (top + rng.gen_range(1..4)) % 4);
+                    line.push(CLOSERS[wrong]);
+                    // Anything goes after the first illegal character
+                    line.extend((j..len).map(|_| *b"()[]{}<>".choose(rng).unwrap() as char));
+                    break;
+                }
+                // Closing scores are base 5, deep stacks would overflow them
+                if !stack.is_empty() && (stack.len() >= 20 || rng.gen_bool(0.45)) {
+                    line.push(CLOSERS[stack.pop().unwrap()]);
+                } else {
+                    let opener = rng.gen_range(0..4);
+                    stack.push(opener);
+                    line.push(OPENERS[opener]);
+                }
+            }
+            if !corrupted && stack.is_empty() {
+                line.push(OPENERS[rng.gen_range(0..4)]);
+            }
+            line
+        })
+        .join("\n")
+}
+
+fn day11(rng: &mut ChaCha8Rng, size: usize) -> String {
+    // Not every grid ends up flashing all at once, keep trying until one does
+    loop {
+        let grid = Grid::from_fn(size, size, |_| rng.gen_range(0..10u8));
+        if octopuses_synchronize(&grid, 100 * size * size) {
+            return grid.render(|&e| char::from(b'0' + e)).trim_end().to_string();
+        }
+    }
+}
+
+fn octopuses_synchronize(grid: &Grid<u8>, max_steps: usize) -> bool {
+    let mut grid = grid.clone();
+    for _ in 0..max_steps {
+        let mut flashing: Vec<_> = grid.positions().collect();
+        for cell in grid.cells_mut() {
+            *cell += 1;
+        }
+        flashing.retain(|&p| grid[p] > 9);
+        let mut flashed = 0;
+        while let Some(pos) = flashing.pop() {
+            flashed += 1;
+            for n in grid.neighbours(pos, Neighbours::Eight) {
+                grid[n] += 1;
+                if grid[n] == 10 {
+                    flashing.push(n);
+                }
+            }
+        }
+        for cell in grid.cells_mut() {
+            if *cell > 9 {
+                *cell = 0;
+            }
+        }
+        if flashed == grid.len() {
+            return true;
+        }
+    }
+    false
+}
+
+fn day12(rng: &mut ChaCha8Rng, size: usize) -> String {
+    // Caves are identified by the product of their characters, names must not collide
+    let mut products = HashSet::new();
+    let mut name = |rng: &mut ChaCha8Rng, big: bool| loop {
+        let base = if big { b'A' } else { b'a' };
+        let name: String = (0..2).map(|_| char::from(base + rng.gen_range(0..26))).collect();
+        if products.insert(name.chars().map(|c| c as i64).product::<i64>() * if big { 1 } else { -1 }) {
+            return name;
+        }
+    };
+    let big: Vec<String> = (0..size / 4).map(|_| name(rng, true)).collect();
+    let small: Vec<String> = (0..size - big.len()).map(|_| name(rng, false)).collect();
+
+    // Two big caves next to each other would make for infinitely many paths, so every edge has a small end
+    let mut edges = BTreeSet::new();
+    for (i, cave) in small.iter().enumerate() {
+        let candidates: Vec<&str> = std::iter::once("start").chain(small[..i].iter().map(String::as_str)).collect();
+        edges.insert((candidates.choose(rng).unwrap().to_string(), cave.clone()));
+    }
+    for cave in &big {
+        for _ in 0..rng.gen_range(2..=3) {
+            edges.insert((small.choose(rng).unwrap().clone(), cave.clone()));
+        }
+    }
+    for _ in 0..2 {
+        let caves: Vec<&String> = small.iter().chain(&big).collect();
+        edges.insert((caves.choose(rng).unwrap().to_string(), "end".to_string()));
+    }
+    for _ in 0..size / 3 {
+        let (a, b) = (small.choose(rng).unwrap(), small.choose(rng).unwrap());
+        if a != b {
+            edges.insert((a.clone(), b.clone()));
+        }
+    }
+    let mut edges: Vec<_> = edges
+        .into_iter()
+        .map(|(a, b)| if rng.gen() { format!("{}-{}", a, b) } else { format!("{}-{}", b, a) })
+        .collect();
+    edges.shuffle(rng);
+    edges.join("\n")
+}
+
+fn day13(rng: &mut ChaCha8Rng, size: usize) -> String {
+    // Unfold the 40x6 code one fold at a time, each fold doubling the paper and adding the fold line
+    let (mut width, mut height) = (40, 6);
+    let mut folds = vec![];
+    for along_x in (0..5).map(|_| true).chain((0..7).map(|_| false)).collect_vec().choose_multiple(rng, 12) {
+        if *along_x {
+            folds.push(('x', width));
+            width = 2 * width + 1;
+        } else {
+            folds.push(('y', height));
+            height = 2 * height + 1;
+        }
+    }
+    folds.reverse();
+
+    // Each dot is a cell of the code, mirrored or not by every fold, so never on a fold line
+    let mut code: Vec<(usize, usize)> = (0..40).cartesian_product(0..6).filter(|_| rng.gen_bool(0.4)).collect();
+    if code.is_empty() {
+        code.push((0, 0));
+    }
+    let mut dots = BTreeSet::new();
+    while dots.len() < size.min(code.len() << folds.len()) {
+        let (mut x, mut y) = *code.choose(rng).unwrap();
+        for &(axis, line) in folds.iter().rev() {
+            let coord = if axis == 'x' { &mut x } else { &mut y };
+            if rng.gen() {
+                *coord = 2 * line - *coord;
+            }
+        }
+        dots.insert((x, y));
+    }
+    let mut dots: Vec<_> = dots.into_iter().collect();
+    dots.shuffle(rng);
+    format!(
+        "{}\n\n{}",
+        dots.iter().map(|(x, y)| format!("{},{}", x, y)).join("\n"),
+        folds.iter().map(|(axis, line)| format!("fold along {}={}", axis, line)).join("\n")
+    )
+}
+
+fn day14(rng: &mut ChaCha8Rng, size: usize) -> String {
+    let mut letters: Vec<char> = ('A'..='Z').collect();
+    letters.shuffle(rng);
+    letters.truncate(10);
+    let template: String = (0..size.max(2)).map(|_| *letters.choose(rng).unwrap()).collect();
+    // Every pair has a rule, like in the real inputs
+    let rules = letters
+        .iter()
+        .cartesian_product(&letters)
+        .map(|(a, b)| format!("{}{} -> {}", a, b, letters.choose(rng).unwrap()))
+        .join("\n");
+    format!("{}\n\n{}", template, rules)
+}
+
+fn day15(rng: &mut ChaCha8Rng, size: usize) -> String {
+    (0..size).map(|_| (0..size).map(|_| char::from(b'0' + rng.gen_range(1..=9))).collect::<String>()).join("\n")
+}
+
+fn day16(rng: &mut ChaCha8Rng, size: usize) -> String {
+    let mut budget = size;
+    let (mut bits, _) = packet(rng, &mut budget, 0);
+    while bits.len() % 4 != 0 {
+        bits.push('0');
+    }
+    bits.as_bytes()
+        .chunks(4)
+        .map(|nibble| format!("{:X}", u8::from_str_radix(std::str::from_utf8(nibble).unwrap(), 2).unwrap()))
+        .collect()
+}
+
+/// A BITS packet and its value, using at most `budget` packets
+fn packet(rng: &mut ChaCha8Rng, budget: &mut usize, depth: usize) -> (String, u64) {
+    *budget = budget.saturating_sub(1);
+    let version = rng.gen_range(0..8u8);
+    if *budget == 0 || depth >= 12 || rng.gen_bool(0.3) {
+        let groups = rng.gen_range(1..=4);
+        let value: u64 = rng.gen_range(0..1 << (4 * groups));
+        let groups = format!("{:b}", value).len().div_ceil(4);
+        let mut bits = format!("{:03b}100", version);
+        for i in (0..groups).rev() {
+            bits += &format!("{}{:04b}", u8::from(i > 0), (value >> (4 * i)) & 0xf);
+        }
+        return (bits, value);
+    }
+
+    let mut type_id = *[0, 1, 2, 3, 5, 6, 7].choose(rng).unwrap();
+    let count = if type_id >= 5 { 2 } else { rng.gen_range(1..=4) };
+    let (mut children, mut values) = (String::new(), vec![]);
+    for _ in 0..count {
+        let (bits, value) = packet(rng, budget, depth + 1);
+        children += &bits;
+        values.push(value);
+    }
+    // Keep the value in range of the solution's usize
+    if type_id == 1 && values.iter().try_fold(1u64, |acc, &v| acc.checked_mul(v)).is_none() {
+        type_id = 3;
+    }
+    let value = match type_id {
+        0 => values.iter().sum(),
+        1 => values.iter().product(),
+        2 => *values.iter().min().unwrap(),
+        3 => *values.iter().max().unwrap(),
+        5 => u64::from(values[0] > values[1]),
+        6 => u64::from(values[0] < values[1]),
+        _ => u64::from(values[0] == values[1]),
+    };
+    let length = if children.len() < 1 << 15 && rng.gen() {
+        format!("0{:015b}", children.len())
+    } else {
+        format!("1{:011b}", count)
+    };
+    (format!("{:03b}{:03b}{}{}", version, type_id, length, children), value)
+}
+
+fn day17(rng: &mut ChaCha8Rng, size: usize) -> String {
+    let size = size.max(10) as i32;
+    let x1 = rng.gen_range(size / 2..=size);
+    let x2 = x1 + rng.gen_range(size / 10..=size / 5);
+    let y1 = -rng.gen_range(size / 2..=size * 3 / 4);
+    let y2 = y1 + rng.gen_range(size / 10..=size / 4);
+    format!("target area: x={}..{}, y={}..{}", x1, x2, y1, y2)
+}
+
+fn day18(rng: &mut ChaCha8Rng, size: usize) -> String {
+    (0..size.max(2)).map(|_| snailfish(rng, 1)).join("\n")
+}
+
+/// A reduced snailfish pair: nested at most 4 deep, with regular numbers below 10
+fn snailfish(rng: &mut ChaCha8Rng, depth: usize) -> String {
+    let item = |rng: &mut ChaCha8Rng| {
+        if depth < 4 && rng.gen_bool(0.6) {
+            snailfish(rng, depth + 1)
+        } else {
+            rng.gen_range(0..10).to_string()
+        }
+    };
+    let left = item(rng);
+    format!("[{},{}]", left, item(rng))
+}
+
+fn day19(rng: &mut ChaCha8Rng, size: usize) -> String {
+    type Pos = [i32; 3];
+    let in_range = |beacon: &Pos, scanner: &Pos| (0..3).all(|i| (beacon[i] - scanner[i]).abs() <= 1000);
+
+    let mut scanners: Vec<Pos> = vec![[0, 0, 0]];
+    let mut beacons: BTreeSet<Pos> = BTreeSet::new();
+    for i in 0..size.max(2) {
+        if i > 0 {
+            // Each scanner overlaps with an earlier one, sharing at least 12 beacons with it
+            let parent = *scanners.choose(rng).unwrap();
+            let scanner: Pos = parent.map(|c| c + rng.gen_range(-1200..=1200));
+            let lo = [0, 1, 2].map(|i| parent[i].max(scanner[i]) - 1000);
+            let hi = [0, 1, 2].map(|i| parent[i].min(scanner[i]) + 1000);
+            while beacons.iter().filter(|b| in_range(b, &parent) && in_range(b, &scanner)).count() < 12 {
+                beacons.insert([0, 1, 2].map(|i| rng.gen_range(lo[i]..=hi[i])));
+            }
+            scanners.push(scanner);
+        }
+        let scanner = scanners[i];
+        for _ in 0..rng.gen_range(5..15) {
+            beacons.insert(scanner.map(|c| c + rng.gen_range(-1000..=1000)));
+        }
+    }
+
+    // Each scanner sees the beacons in its range, relative to itself and in its own orientation
+    scanners
+        .iter()
+        .enumerate()
+        .map(|(i, scanner)| {
+            let rotation = *ROTATIONS.choose(rng).unwrap();
+            let mut seen: Vec<Pos> = beacons
+                .iter()
+                .filter(|b| in_range(b, scanner))
+                .map(|b| rotation.map(|(axis, sign)| (b[axis] - scanner[axis]) * sign))
+                .collect();
+            seen.shuffle(rng);
+            let seen = seen.iter().map(|b| b.iter().join(",")).join("\n");
+            format!("--- scanner {} ---\n{}", i, seen)
+        })
+        .join("\n\n")
+}
+
+/// The 24 orientations, as the source axis and sign of each coordinate
+const ROTATIONS: [[(usize, i32); 3]; 24] = {
+    let mut rotations = [[(0, 1); 3]; 24];
+    let perms = [[0, 1, 2], [1, 2, 0], [2, 0, 1], [0, 2, 1], [2, 1, 0], [1, 0, 2]];
+    let mut n = 0;
+    let mut p = 0;
+    while p < 6 {
+        let mut signs = 0;
+        while signs < 8 {
+            let s = [1 - 2 * (signs & 1), 1 - 2 * ((signs >> 1) & 1), 1 - 2 * ((signs >> 2) & 1)];
+            // Even permutations keep the handedness with an even number of flips, odd ones with an odd number
+            let parity = if p < 3 { 1 } else { -1 };
+            if s[0] * s[1] * s[2] == parity {
+                rotations[n] = [(perms[p][0], s[0]), (perms[p][1], s[1]), (perms[p][2], s[2])];
+                n += 1;
+            }
+            signs += 1;
+        }
+        p += 1;
+    }
+    rotations
+};
+
+fn day20(rng: &mut ChaCha8Rng, size: usize) -> String {
+    let mut model: Vec<bool> = (0..512).map(|_| rng.gen()).collect();
+    // An infinite number of lit pixels can't be counted, so lit surroundings go dark again on the next step
+    if model[0] {
+        model[511] = false;
+    }
+    let pixel = |b: &bool| if *b { '#' } else { '.' };
+    let image = (0..size).map(|_| (0..size).map(|_| pixel(&rng.gen())).collect::<String>()).join("\n");
+    format!("{}\n\n{}", model.iter().map(pixel).collect::<String>(), image)
+}
+
+fn day21(rng: &mut ChaCha8Rng) -> String {
+    format!(
+        "Player 1 starting position: {}\nPlayer 2 starting position: {}",
+        rng.gen_range(1..=10),
+        rng.gen_range(1..=10)
+    )
+}
+
+fn day22(rng: &mut ChaCha8Rng, size: usize) -> String {
+    (0..size)
+        .map(|i| {
+            // The first steps are in the initialization region, like the real inputs
+            let (bound, max_len) = if i < 20 { (50, 50) } else { (1000, 400) };
+            let on = i == 0 || rng.gen_bool(0.6);
+            let range = |rng: &mut ChaCha8Rng| {
+                let start = rng.gen_range(-bound..=bound);
+                format!("{}..{}", start, (start + rng.gen_range(0..=max_len)).min(bound))
+            };
+            let (x, y, z) = (range(rng), range(rng), range(rng));
+            format!("{} x={},y={},z={}", if on { "on" } else { "off" }, x, y, z)
+        })
+        .join("\n")
+}
+
+fn day23(rng: &mut ChaCha8Rng, size: usize) -> String {
+    let mut amphipods: Vec<char> = "ABCD".chars().flat_map(|c| std::iter::repeat_n(c, size)).collect();
+    amphipods.shuffle(rng);
+    let rows = amphipods.chunks(4).enumerate().map(|(i, row)| {
+        let edge = if i == 0 { "###" } else { "  #" };
+        format!("{}{}{}", edge, row.iter().join("#"), if i == 0 { "###" } else { "#" })
+    });
+    ["#############".to_string(), "#...........#".to_string()]
+        .into_iter()
+        .chain(rows)
+        .chain(std::iter::once("  #########".to_string()))
+        .join("\n")
+}
+
+fn day24(rng: &mut ChaCha8Rng) -> String {
+    // MONAD pushes a digit on a base 26 stack in 7 of its blocks, and pops it in the other 7, requiring the popped
+    // digit plus an offset to match the current one
+    let mut pushes = vec![];
+    let mut open = 0;
+    for remaining in (1..=14).rev() {
+        // Every digit pushed must still be popped by the remaining blocks
+        let push = open == 0 || (open + 1 < remaining && rng.gen());
+        open = if push { open + 1 } else { open - 1 };
+        pushes.push(push);
+    }
+    let mut stack = vec![];
+    pushes
+        .into_iter()
+        .map(|push| {
+            let (div, x, y) = if push {
+                let y = rng.gen_range(0..=16);
+                stack.push(y);
+                (1, rng.gen_range(10..=16), y)
+            } else {
+                let pushed = stack.pop().unwrap();
+                (26, rng.gen_range((-8 - pushed).max(-16)..=(8 - pushed).min(0)), rng.gen_range(0..=16))
+            };
+            format!(
+                "inp w\nmul x 0\nadd x z\nmod x 26\ndiv z {}\nadd x {}\neql x w\neql x 0\nmul y 0\nadd y 25\nmul y x\n\
+                 add y 1\nmul z y\nmul y 0\nadd y w\nadd y {}\nmul y x\nadd z y",
+                div, x, y
+            )
+        })
+        .join("\n")
+}
+
+fn day25(rng: &mut ChaCha8Rng, size: usize) -> String {
+    // Herds can keep moving forever on a torus, keep trying until one stops
+    loop {
+        let grid = Grid::from_fn(size.max(2), size.max(2), |_| *b">>>vvv....".choose(rng).unwrap());
+        if cucumbers_stop(&grid, 20 * size) {
+            return grid.render(|&c| c as char).trim_end().to_string();
+        }
+    }
+}
+
+fn cucumbers_stop(grid: &Grid<u8>, max_steps: usize) -> bool {
+    let mut grid = grid.clone();
+    for _ in 0..max_steps {
+        let mut moved = false;
+        for (herd, (dx, dy)) in [(b'>', (1, 0)), (b'v', (0, 1))] {
+            let before = grid.clone();
+            for (pos, _) in before.iter().filter(|(_, &c)| c == herd) {
+                let next = grid.wrap((pos.0 as isize + dx, pos.1 as isize + dy));
+                if before[next] == b'.' {
+                    grid[pos] = b'.';
+                    grid[next] = herd;
+                    moved = true;
+                }
+            }
+        }
+        if !moved {
+            return true;
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::days;
+
+    #[test]
+    fn same_seed_same_input() {
+        for day in 1..=25 {
+            assert_eq!(generate(day, 5, 42), generate(day, 5, 42));
+        }
+        assert_ne!(generate(4, 5, 1), generate(4, 5, 2));
+        assert_eq!(generate(26, 5, 1), None);
+    }
+
+    #[test]
+    fn every_day_solves_generated_inputs() {
+        // Small sizes, debug builds are slow
+        let sizes = [50, 50, 50, 5, 50, 20, 50, 10, 10, 10, 6, 6, 30, 5, 10, 10, 30, 5, 3, 10, 0, 40, 1, 0, 10];
+        for seed in 0..2 {
+            for solver in days::registry() {
+                let input = generate(solver.day(), sizes[solver.day() as usize - 1], seed).unwrap();
+                if let Err(e) = solver.run(&input) {
+                    panic!("day {} seed {}: {}", solver.day(), seed, e.diagnostic());
+                }
+            }
+        }
+    }
+}
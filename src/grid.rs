@@ -0,0 +1,245 @@
+use std::ops::{Index, IndexMut};
+
+use crate::parse::{Input, ParseError};
+
+/// Which cells count as adjacent to a given cell
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Neighbours {
+    /// Up, down, left and right
+    Four,
+    /// Orthogonal and diagonal
+    Eight,
+}
+
+const FOUR: [(isize, isize); 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
+const EIGHT: [(isize, isize); 8] = [(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)];
+
+impl Neighbours {
+    pub fn offsets(self) -> &'static [(isize, isize)] {
+        match self {
+            Neighbours::Four => &FOUR,
+            Neighbours::Eight => &EIGHT,
+        }
+    }
+}
+
+/// A dense, row-major 2D grid. Positions are `(x, y)`, with `(0, 0)` being the top left cell.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Grid<T> {
+    cells: Vec<T>,
+    width: usize,
+    height: usize,
+}
+
+impl<T> Grid<T> {
+    /// Builds a grid from row-major cells. Panics if `cells` can't be split in rows of `width`.
+    pub fn from_vec(width: usize, cells: Vec<T>) -> Self {
+        assert!(width > 0 && cells.len().is_multiple_of(width), "cells don't fit a grid of width {}", width);
+        Grid {
+            height: cells.len() / width,
+            width,
+            cells,
+        }
+    }
+
+    pub fn from_rows(rows: Vec<Vec<T>>) -> Self {
+        let width = rows.first().map_or(0, Vec::len);
+        assert!(rows.iter().all(|r| r.len() == width), "rows have different lengths");
+        Grid::from_vec(width, rows.into_iter().flatten().collect())
+    }
+
+    pub fn from_fn(width: usize, height: usize, mut f: impl FnMut((usize, usize)) -> T) -> Self {
+        let cells = (0..height).flat_map(|y| (0..width).map(move |x| (x, y))).map(&mut f).collect();
+        Grid::from_vec(width, cells)
+    }
+
+    /// Parses a character map, like `#..#.`, checking that the input is rectangular and only contains characters
+    /// from `allowed`.
+    pub fn parse(input: &Input, text: &str, allowed: &str, mut f: impl FnMut(char) -> T) -> Result<Self, ParseError> {
+        let width = input.rectangular(text.lines())?;
+        let mut cells = Vec::with_capacity(width * text.lines().count());
+        for line in text.lines() {
+            cells.extend(input.chars(line, allowed)?.into_iter().map(&mut f));
+        }
+        Ok(Grid::from_vec(width, cells))
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn contains(&self, (x, y): (isize, isize)) -> bool {
+        x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height
+    }
+
+    /// Bounds-checked access
+    pub fn get(&self, pos: (isize, isize)) -> Option<&T> {
+        self.contains(pos).then(|| &self.cells[pos.0 as usize + pos.1 as usize * self.width])
+    }
+
+    pub fn get_mut(&mut self, pos: (isize, isize)) -> Option<&mut T> {
+        if self.contains(pos) {
+            Some(&mut self.cells[pos.0 as usize + pos.1 as usize * self.width])
+        } else {
+            None
+        }
+    }
+
+    /// Access on an infinite plane, where everything outside the grid is `background`
+    pub fn get_or<'a>(&'a self, pos: (isize, isize), background: &'a T) -> &'a T {
+        self.get(pos).unwrap_or(background)
+    }
+
+    /// Maps any position back into the grid, as if its edges wrapped around
+    pub fn wrap(&self, (x, y): (isize, isize)) -> (usize, usize) {
+        (x.rem_euclid(self.width as isize) as usize, y.rem_euclid(self.height as isize) as usize)
+    }
+
+    pub fn get_wrapping(&self, pos: (isize, isize)) -> &T {
+        &self[self.wrap(pos)]
+    }
+
+    /// Positions adjacent to `pos` that are inside the grid
+    pub fn neighbours(&self, (x, y): (usize, usize), kind: Neighbours) -> impl Iterator<Item = (usize, usize)> {
+        let (width, height) = (self.width as isize, self.height as isize);
+        kind.offsets().iter().filter_map(move |(dx, dy)| {
+            let (nx, ny) = (x as isize + dx, y as isize + dy);
+            (nx >= 0 && ny >= 0 && nx < width && ny < height).then_some((nx as usize, ny as usize))
+        })
+    }
+
+    /// Positions adjacent to `pos`, wrapping around the edges of the grid
+    pub fn neighbours_wrapping(&self, (x, y): (usize, usize), kind: Neighbours) -> impl Iterator<Item = (usize, usize)> {
+        let (width, height) = (self.width as isize, self.height as isize);
+        kind.offsets().iter().map(move |(dx, dy)| {
+            ((x as isize + dx).rem_euclid(width) as usize, (y as isize + dy).rem_euclid(height) as usize)
+        })
+    }
+
+    /// Every position, row by row
+    pub fn positions(&self) -> impl Iterator<Item = (usize, usize)> {
+        let width = self.width;
+        (0..self.cells.len()).map(move |i| (i % width, i / width))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = ((usize, usize), &T)> {
+        self.positions().zip(self.cells.iter())
+    }
+
+    pub fn cells(&self) -> &[T] {
+        &self.cells
+    }
+
+    pub fn cells_mut(&mut self) -> &mut [T] {
+        &mut self.cells
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.cells.chunks(self.width)
+    }
+
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid {
+            cells: self.cells.iter().map(f).collect(),
+            width: self.width,
+            height: self.height,
+        }
+    }
+
+    /// Renders the grid back to a character map, one line per row
+    pub fn render(&self, mut f: impl FnMut(&T) -> char) -> String {
+        let mut buf = String::with_capacity((self.width + 1) * self.height);
+        for row in self.rows() {
+            buf.extend(row.iter().map(&mut f));
+            buf.push('\n');
+        }
+        buf
+    }
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, (x, y): (usize, usize)) -> &T {
+        assert!(x < self.width, "x out of bounds: {} >= {}", x, self.width);
+        &self.cells[x + y * self.width]
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut T {
+        assert!(x < self.width, "x out of bounds: {} >= {}", x, self.width);
+        &mut self.cells[x + y * self.width]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn digits() -> Grid<u8> {
+        Grid::from_rows(vec![vec![1, 2, 3], vec![4, 5, 6]])
+    }
+
+    #[test]
+    fn bounds_checked_access() {
+        let grid = digits();
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert_eq!(grid[(2, 1)], 6);
+        assert_eq!(grid.get((2, 1)), Some(&6));
+        assert_eq!(grid.get((3, 0)), None);
+        assert_eq!(grid.get((0, -1)), None);
+        assert_eq!(*grid.get_or((-5, 9), &0), 0);
+    }
+
+    #[test]
+    fn neighbours_stay_inside() {
+        let grid = digits();
+        let four: Vec<_> = grid.neighbours((0, 0), Neighbours::Four).collect();
+        assert_eq!(four, vec![(1, 0), (0, 1)]);
+        let eight: Vec<_> = grid.neighbours((2, 0), Neighbours::Eight).collect();
+        assert_eq!(eight, vec![(1, 0), (1, 1), (2, 1)]);
+        assert_eq!(grid.neighbours((1, 1), Neighbours::Eight).count(), 5);
+    }
+
+    #[test]
+    fn wrapping() {
+        let grid = digits();
+        assert_eq!(grid.wrap((-1, 2)), (2, 0));
+        assert_eq!(*grid.get_wrapping((3, -1)), 4);
+        let four: Vec<_> = grid.neighbours_wrapping((0, 0), Neighbours::Four).collect();
+        assert_eq!(four, vec![(0, 1), (2, 0), (1, 0), (0, 1)]);
+    }
+
+    #[test]
+    fn parse_and_render() {
+        let text = "#..\n.#.";
+        let input = Input::new(0, text);
+        let grid = Grid::parse(&input, text, "#.", |c| c == '#').unwrap();
+        assert_eq!(grid.iter().filter(|(_, &b)| b).map(|(p, _)| p).collect::<Vec<_>>(), vec![(0, 0), (1, 1)]);
+        assert_eq!(grid.render(|&b| if b { '#' } else { '.' }), "#..\n.#.\n");
+
+        let text = "#..\n.x.";
+        let err = Grid::parse(&Input::new(0, text), text, "#.", |c| c == '#').unwrap_err();
+        assert_eq!((err.line, err.column), (2, 2));
+    }
+
+    #[test]
+    fn from_fn_is_row_major() {
+        let grid = Grid::from_fn(3, 2, |(x, y)| x + 10 * y);
+        assert_eq!(grid.cells(), &[0, 1, 2, 10, 11, 12]);
+        assert_eq!(grid.map(|v| v * 2)[(1, 1)], 22);
+    }
+}
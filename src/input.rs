@@ -0,0 +1,224 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::verify::fingerprint;
+
+/// Somewhere puzzle inputs can be downloaded from
+pub trait Fetcher {
+    fn fetch(&self, day: u8) -> Result<String, Box<dyn Error>>;
+}
+
+/// Downloads inputs from adventofcode.com, or anything serving the same routes
+pub struct HttpFetcher {
+    /// Base URL, like `https://adventofcode.com`
+    pub endpoint: String,
+    pub year: u16,
+    /// Value of the `session` cookie of a logged in user
+    pub session: String,
+}
+
+impl HttpFetcher {
+    pub fn url(&self, day: u8) -> String {
+        format!("{}/{}/day/{}/input", self.endpoint.trim_end_matches('/'), self.year, day)
+    }
+}
+
+impl Fetcher for HttpFetcher {
+    fn fetch(&self, day: u8) -> Result<String, Box<dyn Error>> {
+        let url = self.url(day);
+        let response = ureq::get(&url)
+            .set("Cookie", &format!("session={}", self.session))
+            .set("User-Agent", concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION")))
+            .call()
+            .map_err(|e| match e {
+                ureq::Error::Status(status, _) => format!("fetching {} failed with status {}", url, status),
+                e => format!("fetching {} failed: {}", url, e),
+            })?;
+        Ok(response.into_string()?)
+    }
+}
+
+/// Inputs stored on disk as `day<N>.txt`, like cargo-aoc does, with their fingerprints in `checksums.toml`
+pub struct Cache {
+    dir: PathBuf,
+}
+
+impl Cache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Cache { dir: dir.into() }
+    }
+
+    pub fn path(&self, day: u8) -> PathBuf {
+        self.dir.join(format!("day{}.txt", day))
+    }
+
+    fn checksums_path(&self) -> PathBuf {
+        self.dir.join("checksums.toml")
+    }
+
+    fn checksums(&self) -> Result<BTreeMap<String, String>, Box<dyn Error>> {
+        match fs::read_to_string(self.checksums_path()) {
+            Ok(text) => Ok(toml::from_str(&text)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// The cached input for `day`, if any. Inputs added by hand have no checksum and are trusted as is.
+    pub fn load(&self, day: u8) -> Result<Option<String>, Box<dyn Error>> {
+        let text = match fs::read_to_string(self.path(day)) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        match self.checksums()?.get(&format!("day{}", day)) {
+            Some(expected) if *expected != fingerprint(&text) => Err(format!(
+                "{} does not match its checksum {}, delete it to fetch it again",
+                self.path(day).display(),
+                expected
+            )
+            .into()),
+            _ => Ok(Some(text)),
+        }
+    }
+
+    pub fn store(&self, day: u8, text: &str) -> Result<(), Box<dyn Error>> {
+        fs::create_dir_all(&self.dir)?;
+        fs::write(self.path(day), text)?;
+        let mut checksums = self.checksums()?;
+        checksums.insert(format!("day{}", day), fingerprint(text));
+        fs::write(self.checksums_path(), toml::to_string(&checksums)?)?;
+        Ok(())
+    }
+}
+
+/// Where the `run` command gets its inputs from
+pub enum Provider {
+    /// The same text for every day
+    Stdin(String),
+    File(PathBuf),
+    /// Inputs from the cache, fetching the missing ones if a fetcher is configured. Cached days are never fetched
+    /// again.
+    Cached {
+        cache: Cache,
+        fetcher: Option<Box<dyn Fetcher>>,
+    },
+}
+
+impl Provider {
+    pub fn get(&self, day: u8) -> Result<String, Box<dyn Error>> {
+        match self {
+            Provider::Stdin(text) => Ok(text.clone()),
+            Provider::File(path) => Ok(fs::read_to_string(path)?),
+            Provider::Cached { cache, fetcher } => {
+                if let Some(text) = cache.load(day)? {
+                    return Ok(text);
+                }
+                let fetcher = fetcher.as_ref().ok_or_else(|| {
+                    format!("{} not found, and no session to fetch it (set AOC_SESSION)", cache.path(day).display())
+                })?;
+                let text = fetcher.fetch(day)?;
+                cache.store(day, &text)?;
+                Ok(text)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("aoc-input-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    /// Request line and cookie of each request received
+    type Requests = Arc<Mutex<Vec<(String, String)>>>;
+
+    /// Serves `/2021/day/<N>/input` for days 1 to 9
+    fn mock_server() -> (String, Requests) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(vec![]));
+        let log = requests.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let (mut request, mut cookie) = (String::new(), String::new());
+                reader.read_line(&mut request).unwrap();
+                loop {
+                    let mut header = String::new();
+                    reader.read_line(&mut header).unwrap();
+                    if header.trim().is_empty() {
+                        break;
+                    }
+                    if let Some(value) = header.strip_prefix("Cookie: ") {
+                        cookie = value.trim().to_string();
+                    }
+                }
+                let request = request.trim().to_string();
+                let day = request.strip_prefix("GET /2021/day/").and_then(|r| r.strip_suffix("/input HTTP/1.1"));
+                let response = match day {
+                    Some(day) if day.len() == 1 && cookie == "session=secret" => {
+                        let body = format!("input for day {}\n", day);
+                        format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}", body.len(), body)
+                    }
+                    _ => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n".to_string(),
+                };
+                log.lock().unwrap().push((request, cookie));
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        (endpoint, requests)
+    }
+
+    #[test]
+    fn fetches_missing_days_once() {
+        let (endpoint, requests) = mock_server();
+        let dir = temp_dir("fetch");
+        let fetcher = HttpFetcher { endpoint, year: 2021, session: "secret".to_string() };
+        let provider = Provider::Cached { cache: Cache::new(&dir), fetcher: Some(Box::new(fetcher)) };
+
+        assert_eq!(provider.get(3).unwrap(), "input for day 3\n");
+        assert_eq!(provider.get(3).unwrap(), "input for day 3\n");
+        assert_eq!(
+            *requests.lock().unwrap(),
+            vec![("GET /2021/day/3/input HTTP/1.1".to_string(), "session=secret".to_string())]
+        );
+        let checksums = fs::read_to_string(dir.join("checksums.toml")).unwrap();
+        assert!(checksums.contains(&fingerprint("input for day 3\n")));
+
+        assert!(provider.get(12).is_err());
+        assert!(!dir.join("day12.txt").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn rejects_corrupted_inputs() {
+        let dir = temp_dir("corrupt");
+        let cache = Cache::new(&dir);
+        assert_eq!(cache.load(1).unwrap(), None);
+        cache.store(1, "199\n200").unwrap();
+        assert_eq!(cache.load(1).unwrap().as_deref(), Some("199\n200"));
+
+        fs::write(cache.path(1), "199\n201").unwrap();
+        assert!(cache.load(1).is_err());
+        // Inputs added by hand don't have a checksum
+        fs::write(cache.path(2), "forward 5").unwrap();
+        assert_eq!(cache.load(2).unwrap().as_deref(), Some("forward 5"));
+
+        let provider = Provider::Cached { cache, fetcher: None };
+        assert!(provider.get(3).unwrap_err().to_string().contains("AOC_SESSION"));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -5,7 +5,18 @@ macro_rules! example {
     };
 }
 
+pub mod bench;
+pub mod bignum;
+pub mod crosscheck;
 pub mod days;
+pub mod gen;
+pub mod grid;
+pub mod input;
+pub mod parse;
+pub mod render;
+pub mod segments;
+pub mod sim;
+pub mod verify;
 
 #[macro_use]
 extern crate aoc_runner_derive;
@@ -1,4 +1,693 @@
-extern crate aoc_2021;
-use aoc_runner_derive::aoc_main;
+use std::error::Error;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
 
-aoc_main! { lib = aoc_2021 }
+use aoc_2021::bench::{self, BenchRun, Settings};
+use aoc_2021::crosscheck;
+use aoc_2021::days::{self, Solver};
+use aoc_2021::gen;
+use aoc_2021::input::{Cache, HttpFetcher, Provider};
+use aoc_2021::parse::ParseError;
+use aoc_2021::render::{self, Format};
+use aoc_2021::verify::{self, Answers, Outcome};
+use clap::{Args, Parser, Subcommand};
+
+#[derive(Parser)]
+#[command(about = "Advent of Code 2021 solutions")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Lists every registered solution
+    List,
+    /// Runs solutions on a puzzle input
+    Run {
+        /// Day to run, every day if omitted
+        #[arg(long)]
+        day: Option<u8>,
+        /// Part to run, both if omitted
+        #[arg(long)]
+        part: Option<u8>,
+        /// Named variant to run (e.g. `points`), the default one if omitted
+        #[arg(long)]
+        variant: Option<String>,
+        /// Input file, or `-` to read from stdin. Defaults to the cached input, fetched if missing
+        #[arg(long)]
+        input: Option<PathBuf>,
+        /// Directory to write an animation of each day to, for the days that have one (11, 13, 15, 20 and 25)
+        #[arg(long)]
+        render: Option<PathBuf>,
+        /// How animations are written
+        #[arg(long, value_enum, default_value_t = Format::Gif)]
+        render_format: Format,
+        #[command(flatten)]
+        source: InputSource,
+    },
+    /// Downloads the inputs missing from the cache
+    Fetch {
+        /// Only fetch this day
+        #[arg(long)]
+        day: Option<u8>,
+        #[command(flatten)]
+        source: InputSource,
+    },
+    /// Checks every solution against the known answers for the stored inputs
+    Verify {
+        /// Answers file, keyed by day and input fingerprint
+        #[arg(long, default_value = "answers.toml")]
+        answers: PathBuf,
+        /// Directory of real inputs (`day5.txt`, `day5-alice.txt`, ...)
+        #[arg(long, default_value = "input/2021")]
+        inputs: PathBuf,
+        /// Only verify this day
+        #[arg(long)]
+        day: Option<u8>,
+        /// Record the default variant's answer for inputs that don't have one yet
+        #[arg(long)]
+        record: bool,
+    },
+    /// Prints a random, valid input for a day
+    Gen {
+        #[arg(long)]
+        day: u8,
+        /// What this scales depends on the day (scanners for day 19, cave side for day 15, ...). Defaults to the size
+        /// of the real inputs
+        #[arg(long)]
+        size: Option<usize>,
+        /// The same seed always gives the same input
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+        /// File to write the input to, instead of stdout
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    /// Times the generator and solution of every variant, comparing against a saved baseline
+    Bench {
+        /// Only benchmark this day
+        #[arg(long)]
+        day: Option<u8>,
+        /// Directory of inputs, named `day<N>.txt`
+        #[arg(long, default_value = "input/2021")]
+        inputs: PathBuf,
+        /// Directory the results of each run are written to
+        #[arg(long, default_value = "bench")]
+        history: PathBuf,
+        /// Results to compare against
+        #[arg(long, default_value = "bench/baseline.json")]
+        baseline: PathBuf,
+        /// Slowdown, in percent, above which a solution is reported as a regression
+        #[arg(long, default_value_t = 10.0)]
+        threshold: f64,
+        /// Replace the baseline with the results of this run
+        #[arg(long)]
+        save_baseline: bool,
+        /// Minimum number of runs per solution
+        #[arg(long, default_value_t = 5)]
+        min_iterations: usize,
+        /// Time spent measuring each solution, in milliseconds, once the minimum number of runs is reached
+        #[arg(long, default_value_t = 1000)]
+        budget_ms: u64,
+    },
+    /// Runs a submarine script, like day 2: its commands, plus comments, `repeat N { }` blocks and `macro name { }`
+    Dive {
+        /// Script file, or `-` to read from stdin
+        #[arg(long, default_value = "-")]
+        script: PathBuf,
+        /// How commands move the submarine: `simple` (part 1), `aim` (part 2), `drift` or `bounded`
+        #[arg(long, default_value = "aim")]
+        model: String,
+        /// Print the position, depth and model specific values like the aim after each command
+        #[arg(long)]
+        trace: bool,
+    },
+    /// Counts the increases of a sliding window over a sonar sweep of any size, like day 1, reading it line by line
+    Sonar {
+        /// Number of measurements summed in each window
+        #[arg(long, default_value_t = 3)]
+        window: usize,
+        /// File of measurements, one per line, or `-` to read from stdin
+        #[arg(long, default_value = "-")]
+        input: PathBuf,
+        /// Print the index of every window larger than the previous one
+        #[arg(long)]
+        indices: bool,
+    },
+    /// Prints a JSON report of the runs, plateaus, jumps and anomalies of a depth series, like day 1
+    Depths {
+        /// File of measurements, one per line, or `-` to read from stdin. Defaults to the cached day 1 input
+        #[arg(long)]
+        input: Option<PathBuf>,
+        /// Changes larger than this are reported as anomalies
+        #[arg(long, default_value_t = 100)]
+        threshold: u32,
+        #[command(flatten)]
+        source: InputSource,
+    },
+    /// Runs every variant of a part on random inputs, stopping at the first one they disagree on
+    Crosscheck {
+        /// Only check this day
+        #[arg(long)]
+        day: Option<u8>,
+        /// Number of inputs per part
+        #[arg(long, default_value_t = 100)]
+        cases: usize,
+        /// Largest size of the generated inputs, see `gen`
+        #[arg(long, default_value_t = 20)]
+        max_size: usize,
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+    },
+    /// Exports how many of day 5's vent lines cover each point, as an image or CSV
+    Heatmap {
+        /// File of vent lines, or `-` to read from stdin. Defaults to the cached day 5 input
+        #[arg(long)]
+        input: Option<PathBuf>,
+        /// File to write
+        #[arg(long)]
+        output: PathBuf,
+        #[arg(long, value_enum, default_value_t = HeatmapFormat::Ppm)]
+        format: HeatmapFormat,
+        /// Only export this region, as two opposite corners `x1,y1,x2,y2`
+        #[arg(long)]
+        region: Option<days::Region>,
+        /// Ignore diagonal lines, like part 1
+        #[arg(long)]
+        no_diagonals: bool,
+        /// Pixels per point, for PPM and PNG images
+        #[arg(long, default_value_t = 1)]
+        scale: usize,
+        #[command(flatten)]
+        source: InputSource,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum HeatmapFormat {
+    /// Grayscale image
+    Pgm,
+    /// Color image
+    Ppm,
+    Png,
+    /// The counts, a row per line
+    Csv,
+}
+
+#[derive(Args)]
+struct InputSource {
+    /// Directory inputs are cached in, as `day<N>.txt`
+    #[arg(long, default_value = "input/2021")]
+    cache: PathBuf,
+    /// Server to fetch missing inputs from
+    #[arg(long, env = "AOC_ENDPOINT", default_value = "https://adventofcode.com")]
+    endpoint: String,
+    /// Session cookie used to fetch missing inputs
+    #[arg(long, env = "AOC_SESSION", hide_env_values = true)]
+    session: Option<String>,
+}
+
+impl InputSource {
+    fn provider(self) -> Provider {
+        let fetcher = self.session.map(|session| {
+            Box::new(HttpFetcher { endpoint: self.endpoint, year: 2021, session }) as Box<_>
+        });
+        Provider::Cached { cache: Cache::new(self.cache), fetcher }
+    }
+}
+
+fn main() -> ExitCode {
+    match Cli::parse().command {
+        Command::List => {
+            for solver in days::registry() {
+                println!("{}", describe(solver.as_ref()));
+            }
+            ExitCode::SUCCESS
+        }
+        Command::Run { day, part, variant, input, render, render_format, source } => {
+            let render = render.map(|dir| (dir, render_format));
+            run(day, part, variant.as_deref(), input, render, source)
+        }
+        Command::Fetch { day, source } => fetch(day, source),
+        Command::Verify { answers, inputs, day, record } => match verify(&answers, &inputs, day, record) {
+            Ok(code) => code,
+            Err(e) => {
+                eprintln!("Verification failed: {}", e);
+                ExitCode::FAILURE
+            }
+        },
+        Command::Gen { day, size, seed, output } => {
+            let Some(text) = gen::generate(day, size.unwrap_or_else(|| gen::default_size(day)), seed) else {
+                eprintln!("No generator for day {}", day);
+                return ExitCode::FAILURE;
+            };
+            match output {
+                Some(path) => {
+                    if let Err(e) = std::fs::write(&path, text + "\n") {
+                        eprintln!("Could not write {}: {}", path.display(), e);
+                        return ExitCode::FAILURE;
+                    }
+                }
+                None => println!("{}", text),
+            }
+            ExitCode::SUCCESS
+        }
+        Command::Bench { day, inputs, history, baseline, threshold, save_baseline, min_iterations, budget_ms } => {
+            let settings = Settings {
+                min_iterations,
+                budget: Duration::from_millis(budget_ms),
+                ..Settings::default()
+            };
+            match bench(day, &inputs, &history, &baseline, threshold, save_baseline, &settings) {
+                Ok(code) => code,
+                Err(e) => {
+                    eprintln!("Benchmark failed: {}", e);
+                    ExitCode::FAILURE
+                }
+            }
+        }
+        Command::Dive { script, model, trace } => dive(&script, &model, trace),
+        Command::Sonar { window, input, indices } => match sonar(window, &input, indices) {
+            Ok(count) => {
+                println!("{} increases", count);
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                match e.downcast_ref::<ParseError>() {
+                    Some(e) => eprintln!("Invalid measurement\n{}", e.diagnostic()),
+                    None => eprintln!("Could not read {}: {}", input.display(), e),
+                }
+                ExitCode::FAILURE
+            }
+        },
+        Command::Depths { input, threshold, source } => depths(input, threshold, source),
+        Command::Crosscheck { day, cases, max_size, seed } => {
+            crosscheck(day, &crosscheck::Settings { cases, max_size, seed })
+        }
+        Command::Heatmap { input, output, format, region, no_diagonals, scale, source } => {
+            heatmap(input, &output, format, region, !no_diagonals, scale, source)
+        }
+    }
+}
+
+fn run(
+    day: Option<u8>,
+    part: Option<u8>,
+    variant: Option<&str>,
+    input: Option<PathBuf>,
+    render: Option<(PathBuf, Format)>,
+    source: InputSource,
+) -> ExitCode {
+    let mut solvers = days::registry();
+    solvers.retain(|s| day.is_none_or(|d| s.day() == d) && part.is_none_or(|p| s.part() == p));
+    match variant {
+        Some(name) => solvers.retain(|s| s.variant() == Some(name)),
+        // The first variant registered for each part is the default one
+        None => solvers.dedup_by_key(|s| (s.day(), s.part())),
+    }
+    if solvers.is_empty() {
+        eprintln!("No matching solution");
+        return ExitCode::FAILURE;
+    }
+
+    let provider = match input {
+        Some(path) if path.as_os_str() == "-" => match read_stdin() {
+            Ok(text) => Provider::Stdin(text),
+            Err(e) => {
+                eprintln!("Could not read stdin: {}", e);
+                return ExitCode::FAILURE;
+            }
+        },
+        Some(path) => Provider::File(path),
+        None => source.provider(),
+    };
+
+    let mut failed = false;
+    let mut solvers = solvers.into_iter().peekable();
+    while let Some(solver) = solvers.next() {
+        let next_day = solvers.peek().map(|s| s.day());
+        let text = match provider.get(solver.day()) {
+            Ok(text) => text,
+            Err(e) => {
+                eprintln!("{}: could not read input: {}", describe(solver.as_ref()), e);
+                failed = true;
+                continue;
+            }
+        };
+        let start = Instant::now();
+        // Same as aoc-runner, which strips trailing newlines from inputs
+        match solver.run(text.trim_end_matches('\n')) {
+            Ok(answer) => println!("{}: {} ({:?})", describe(solver.as_ref()), answer, start.elapsed()),
+            Err(e) => {
+                eprintln!("{}: invalid input\n{}", describe(solver.as_ref()), e.diagnostic());
+                failed = true;
+            }
+        }
+        // Each day is animated once, after its last part
+        if let Some((dir, format)) = &render {
+            if next_day != Some(solver.day()) {
+                failed |= !animate(solver.day(), &text, dir, *format);
+            }
+        }
+    }
+    if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+fn animate(day: u8, input: &str, dir: &Path, format: Format) -> bool {
+    let frames = match render::animate(day, input.trim_end_matches('\n')) {
+        Ok(Some(frames)) => frames,
+        Ok(None) => return true,
+        // Already reported by the solution
+        Err(_) => return false,
+    };
+    match render::save(&frames, dir, day, format) {
+        Ok(path) => {
+            println!("Day {}: {} frames written to {}", day, frames.len(), path.display());
+            true
+        }
+        Err(e) => {
+            eprintln!("Day {}: could not write the animation: {}", day, e);
+            false
+        }
+    }
+}
+
+fn fetch(day: Option<u8>, source: InputSource) -> ExitCode {
+    if source.session.is_none() {
+        eprintln!("No session cookie, set AOC_SESSION or pass --session");
+        return ExitCode::FAILURE;
+    }
+    let provider = source.provider();
+    let mut failed = false;
+    for d in (1..=25).filter(|&d| day.is_none_or(|day| day == d)) {
+        match provider.get(d) {
+            Ok(text) => println!("Day {}: {} lines", d, text.lines().count()),
+            Err(e) => {
+                eprintln!("Day {}: {}", d, e);
+                failed = true;
+            }
+        }
+    }
+    if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+fn verify(answers_path: &Path, inputs: &Path, day: Option<u8>, record: bool) -> Result<ExitCode, Box<dyn Error>> {
+    let mut answers = Answers::load(answers_path)?;
+    let mut inputs = verify::stored_inputs(inputs)?;
+    inputs.retain(|i| day.is_none_or(|d| i.day == d));
+
+    let reports = verify::verify(&days::registry(), &inputs, &answers);
+    let (mut correct, mut wrong, mut unknown, mut invalid) = (0, 0, 0, 0);
+    for report in &reports {
+        let name = format!("{} [{}]", describe_parts(report.day, report.part, report.variant), report.input.display());
+        match &report.outcome {
+            Outcome::Correct => {
+                correct += 1;
+                println!("{}: ok", name);
+            }
+            Outcome::Wrong { expected } => {
+                wrong += 1;
+                println!("{}: WRONG, got {} but expected {}", name, report.answer.as_deref().unwrap_or_default(), expected);
+            }
+            Outcome::Unknown => {
+                unknown += 1;
+                let answer = report.answer.clone().unwrap_or_default();
+                if record && answers.get(report.day, &report.fingerprint, report.part).is_none() {
+                    println!("{}: recorded {}", name, answer);
+                    answers.insert(report.day, &report.fingerprint, report.part, answer);
+                } else {
+                    println!("{}: no known answer, got {}", name, answer);
+                }
+            }
+            Outcome::Invalid(e) => {
+                invalid += 1;
+                println!("{}: invalid input\n{}", name, e.diagnostic());
+            }
+        }
+    }
+    if record {
+        answers.save(answers_path)?;
+    }
+    println!("{} correct, {} wrong, {} unknown, {} invalid", correct, wrong, unknown, invalid);
+    Ok(if wrong + invalid > 0 { ExitCode::FAILURE } else { ExitCode::SUCCESS })
+}
+
+fn bench(
+    day: Option<u8>,
+    inputs: &Path,
+    history: &Path,
+    baseline_path: &Path,
+    threshold: f64,
+    save_baseline: bool,
+    settings: &Settings,
+) -> Result<ExitCode, Box<dyn Error>> {
+    let mut solvers = days::registry();
+    solvers.retain(|s| day.is_none_or(|d| s.day() == d));
+
+    let mut run = BenchRun::new();
+    let mut failed = false;
+    for solver in &solvers {
+        let path = inputs.join(format!("day{}.txt", solver.day()));
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) => {
+                eprintln!("{}: skipped, could not read {}: {}", describe(solver.as_ref()), path.display(), e);
+                continue;
+            }
+        };
+        match bench::measure(solver.as_ref(), text.trim_end_matches('\n'), settings) {
+            Ok(m) => {
+                println!(
+                    "{}: generator {:?}, solver {:?} ({} runs)",
+                    describe(solver.as_ref()),
+                    Duration::from_nanos(m.generator_ns),
+                    Duration::from_nanos(m.solver_ns),
+                    m.iterations
+                );
+                run.results.push(m);
+            }
+            Err(e) => {
+                eprintln!("{}: invalid input\n{}", describe(solver.as_ref()), e.diagnostic());
+                failed = true;
+            }
+        }
+    }
+
+    let path = history.join(run.file_name());
+    run.save(&path)?;
+    println!("Results written to {}", path.display());
+
+    if baseline_path.exists() {
+        let baseline = BenchRun::load(baseline_path)?;
+        let regressions = bench::compare(&baseline, &run, threshold);
+        for r in &regressions {
+            println!(
+                "REGRESSION {}: {:?} -> {:?} (+{:.1}%)",
+                describe_parts(r.day, r.part, r.variant.as_deref()),
+                Duration::from_nanos(r.baseline_ns),
+                Duration::from_nanos(r.current_ns),
+                r.percent()
+            );
+        }
+        println!(
+            "{} regressions above {}% against {}",
+            regressions.len(),
+            threshold,
+            baseline.commit.as_deref().unwrap_or("baseline")
+        );
+        failed |= !regressions.is_empty();
+    }
+    if save_baseline {
+        run.save(baseline_path)?;
+        println!("Baseline saved to {}", baseline_path.display());
+    }
+    Ok(if failed { ExitCode::FAILURE } else { ExitCode::SUCCESS })
+}
+
+fn dive(path: &Path, model: &str, trace: bool) -> ExitCode {
+    let models = days::Models::builtin();
+    let Some(mut model) = models.create(model) else {
+        eprintln!("Unknown model `{}`, expected one of: {}", model, models.names().collect::<Vec<_>>().join(", "));
+        return ExitCode::FAILURE;
+    };
+    let text = if path.as_os_str() == "-" { read_stdin() } else { std::fs::read_to_string(path) };
+    let text = match text {
+        Ok(text) => text,
+        Err(e) => {
+            eprintln!("Could not read {}: {}", path.display(), e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let script = match days::Script::parse(text.trim_end_matches('\n')) {
+        Ok(script) => script,
+        Err(e) => {
+            eprintln!("Invalid script\n{}", e.diagnostic());
+            return ExitCode::FAILURE;
+        }
+    };
+    let stdout = std::io::stdout();
+    let mut out = std::io::BufWriter::new(stdout.lock());
+    let result = script.run(model.as_mut(), &mut |line, instruction, sub| {
+        if trace {
+            let extra: String = sub.extra().iter().map(|(name, value)| format!(", {} {}", name, value)).collect();
+            let (position, depth) = (sub.position(), sub.depth());
+            let instruction = instruction.to_string();
+            let _ = writeln!(out, "line {:>4}: {:<12} position {}, depth {}{}", line, instruction, position, depth, extra);
+        }
+    });
+    drop(out);
+    if let Err(e) = result {
+        eprintln!("Could not run the script: {}", e);
+        return ExitCode::FAILURE;
+    }
+    let (position, depth) = (model.position(), model.depth());
+    println!("Position {}, depth {}: {}", position, depth, position as i128 * depth as i128);
+    ExitCode::SUCCESS
+}
+
+fn sonar(window: usize, input: &Path, indices: bool) -> Result<usize, Box<dyn Error>> {
+    if window == 0 {
+        return Err("windows must hold at least one measurement".into());
+    }
+    let stdout = std::io::stdout();
+    let mut out = std::io::BufWriter::new(stdout.lock());
+    let on_increase = |index| {
+        if indices {
+            // Output errors, like a closed pipe, stop the printing but not the count
+            let _ = writeln!(out, "{}", index);
+        }
+    };
+    if input.as_os_str() == "-" {
+        days::stream_increases(std::io::stdin().lock(), window, on_increase)
+    } else {
+        days::stream_increases(std::io::BufReader::new(std::fs::File::open(input)?), window, on_increase)
+    }
+}
+
+/// Reads `input`, stdin if it is `-`, or the cached input of `day` if there is none
+fn read_day_input(day: u8, input: Option<PathBuf>, source: InputSource) -> Result<String, Box<dyn Error>> {
+    match input {
+        Some(path) if path.as_os_str() == "-" => read_stdin().map_err(|e| e.into()),
+        Some(path) => Provider::File(path).get(day),
+        None => source.provider().get(day),
+    }
+}
+
+fn depths(input: Option<PathBuf>, threshold: u32, source: InputSource) -> ExitCode {
+    let text = match read_day_input(1, input, source) {
+        Ok(text) => text,
+        Err(e) => {
+            eprintln!("Could not read the depths: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    match days::DepthReport::parse(text.trim_end_matches('\n'), threshold) {
+        Ok(report) => {
+            println!("{}", serde_json::to_string_pretty(&report).expect("reports serialize"));
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("Invalid measurement\n{}", e.diagnostic());
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn heatmap(
+    input: Option<PathBuf>,
+    output: &Path,
+    format: HeatmapFormat,
+    region: Option<days::Region>,
+    diagonals: bool,
+    scale: usize,
+    source: InputSource,
+) -> ExitCode {
+    let text = match read_day_input(5, input, source) {
+        Ok(text) => text,
+        Err(e) => {
+            eprintln!("Could not read the vents: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let heatmap = match days::Vents::parse(text.trim_end_matches('\n')) {
+        Ok(vents) => match vents.heatmap(diagonals, region) {
+            Ok(heatmap) => heatmap,
+            Err(e) => {
+                eprintln!("Could not draw the heatmap: {}", e);
+                return ExitCode::FAILURE;
+            }
+        },
+        Err(e) => {
+            eprintln!("Invalid vent line\n{}", e.diagnostic());
+            return ExitCode::FAILURE;
+        }
+    };
+    let data = match format {
+        HeatmapFormat::Pgm => Ok(heatmap.to_pgm()),
+        HeatmapFormat::Ppm => Ok(render::heatmap(&heatmap, scale).to_ppm()),
+        HeatmapFormat::Png => render::heatmap(&heatmap, scale).to_png(),
+        HeatmapFormat::Csv => Ok(heatmap.to_csv().into_bytes()),
+    };
+    match data.map_err(Box::<dyn Error>::from).and_then(|data| Ok(std::fs::write(output, data)?)) {
+        Ok(()) => {
+            let (width, height) = (heatmap.counts.width(), heatmap.counts.height());
+            println!("Wrote {}x{} points from {:?} to {}", width, height, heatmap.origin, output.display());
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("Could not write {}: {}", output.display(), e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn crosscheck(day: Option<u8>, settings: &crosscheck::Settings) -> ExitCode {
+    let mut solvers = days::registry();
+    solvers.retain(|s| day.is_none_or(|d| s.day() == d));
+    // Panics are reported as outcomes, the default hook would print every one of them while shrinking
+    std::panic::set_hook(Box::new(|_| {}));
+    let mut failed = false;
+    for group in crosscheck::variant_groups(solvers) {
+        let name = describe_parts(group[0].day(), group[0].part(), None);
+        match crosscheck::check(&group, settings) {
+            Ok(cases) => println!("{}: {} variants agree on {} inputs", name, group.len(), cases),
+            Err(counterexample) => {
+                print!("{}", counterexample);
+                failed = true;
+            }
+        }
+    }
+    if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+fn read_stdin() -> std::io::Result<String> {
+    let mut buf = String::new();
+    std::io::stdin().read_to_string(&mut buf)?;
+    Ok(buf)
+}
+
+fn describe(solver: &dyn Solver) -> String {
+    describe_parts(solver.day(), solver.part(), solver.variant())
+}
+
+fn describe_parts(day: u8, part: u8, variant: Option<&str>) -> String {
+    match variant {
+        Some(variant) => format!("Day {} - Part {} ({})", day, part, variant),
+        None => format!("Day {} - Part {}", day, part),
+    }
+}
@@ -0,0 +1,192 @@
+use std::error::Error;
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+/// A malformed puzzle input, pointing at the offending token
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub day: u8,
+    /// 1-based line number
+    pub line: usize,
+    /// 1-based column, in characters
+    pub column: usize,
+    pub token: String,
+    pub reason: String,
+    /// The whole line containing the token, used for diagnostics
+    pub source_line: String,
+}
+
+impl ParseError {
+    /// Multi-line report quoting the input, with the token underlined
+    pub fn diagnostic(&self) -> String {
+        let number = self.line.to_string();
+        let padding = " ".repeat(number.len());
+        let marker = "^".repeat(self.token.chars().count().max(1));
+        format!(
+            "error: {}\n{}--> day {}, line {}, column {}\n{} |\n{} | {}\n{} | {}{}",
+            self,
+            padding, self.day, self.line, self.column,
+            padding,
+            number, self.source_line,
+            padding, " ".repeat(self.column - 1), marker
+        )
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.token.is_empty() {
+            write!(f, "{}", self.reason)
+        } else {
+            write!(f, "{} (found `{}`)", self.reason, self.token)
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+/// Puzzle input for a day, used by generators to report errors at the right location.
+///
+/// Tokens passed to [`Input::error`] and [`Input::parse`] must be slices of the input text, their location is
+/// derived from their address.
+#[derive(Copy, Clone)]
+pub struct Input<'a> {
+    day: u8,
+    text: &'a str,
+}
+
+impl<'a> Input<'a> {
+    pub fn new(day: u8, text: &'a str) -> Self {
+        Input { day, text }
+    }
+
+    pub fn error(&self, token: &str, reason: impl Into<String>) -> ParseError {
+        let start = self.text.as_ptr() as usize;
+        let offset = (token.as_ptr() as usize)
+            .checked_sub(start)
+            .filter(|&o| o <= self.text.len())
+            .expect("token is not part of the input");
+
+        let line_start = self.text[..offset].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = self.text[offset..].find('\n').map_or(self.text.len(), |i| offset + i);
+        ParseError {
+            day: self.day,
+            line: self.text[..offset].matches('\n').count() + 1,
+            column: self.text[line_start..offset].chars().count() + 1,
+            token: token.to_string(),
+            reason: reason.into(),
+            source_line: self.text[line_start..line_end].to_string(),
+        }
+    }
+
+    /// Reports a missing token right after `context`
+    pub fn missing(&self, context: &str, what: &str) -> ParseError {
+        self.error(&context[context.len()..], format!("expected {}", what))
+    }
+
+    /// Unwraps a token coming from a split, or reports it missing after `context`
+    pub fn expect<'t>(&self, token: Option<&'t str>, context: &str, what: &str) -> Result<&'t str, ParseError> {
+        token.ok_or_else(|| self.missing(context, what))
+    }
+
+    pub fn parse<T>(&self, token: &str) -> Result<T, ParseError>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        token.parse().map_err(|e: T::Err| self.error(token, e.to_string()))
+    }
+
+    /// Parses a list of numbers separated by `sep`, like `3,4,3,1,2`
+    pub fn parse_list<T>(&self, text: &str, sep: char) -> Result<Vec<T>, ParseError>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        text.split(sep).map(|s| self.parse(s)).collect()
+    }
+
+    /// Returns the single character of `token`, reporting anything longer or shorter
+    pub fn char(&self, token: &str) -> Result<char, ParseError> {
+        let mut chars = token.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(c),
+            _ => Err(self.error(token, "expected a single character")),
+        }
+    }
+
+    /// Splits `text` (a slice of the input) in two around `sep`
+    pub fn split_once<'t>(&self, text: &'t str, sep: &str) -> Result<(&'t str, &'t str), ParseError> {
+        text.split_once(sep)
+            .ok_or_else(|| self.error(text, format!("expected `{}`", sep.escape_debug())))
+    }
+
+    /// Checks that every character of `line` is one of `allowed`, returning them
+    pub fn chars(&self, line: &str, allowed: &str) -> Result<Vec<char>, ParseError> {
+        line.char_indices()
+            .map(|(i, c)| if allowed.contains(c) {
+                Ok(c)
+            } else {
+                Err(self.error(&line[i..(i + c.len_utf8())], format!("expected one of `{}`", allowed)))
+            })
+            .collect()
+    }
+
+    /// Parses a line of single digits, like the rows of a heightmap
+    pub fn digits(&self, line: &str, radix: u32) -> Result<Vec<u8>, ParseError> {
+        line.char_indices()
+            .map(|(i, c)| c.to_digit(radix)
+                .map(|d| d as u8)
+                .ok_or_else(|| self.error(&line[i..(i + c.len_utf8())], "expected a digit")))
+            .collect()
+    }
+
+    /// Checks that `lines` (slices of the input) all have the same length, for grid inputs
+    pub fn rectangular<'t>(&self, lines: impl IntoIterator<Item = &'t str>) -> Result<usize, ParseError> {
+        let mut lines = lines.into_iter();
+        let first = self.expect(lines.next(), self.text, "a grid")?;
+        let len = first.chars().count();
+        for line in lines {
+            if line.chars().count() != len {
+                return Err(self.error(line, format!("expected a row of length {}", len)));
+            }
+        }
+        Ok(len)
+    }
+
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+
+    pub fn lines(&self) -> std::str::Lines<'a> {
+        self.text.lines()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locates_tokens() {
+        let input = Input::new(2, "forward 5\ndown x\nsideways 3");
+        let line = input.lines().nth(2).unwrap();
+        let err = input.error(&line[..8], "unknown instruction");
+        assert_eq!((err.line, err.column), (3, 1));
+        assert_eq!(err.token, "sideways");
+        assert_eq!(err.to_string(), "unknown instruction (found `sideways`)");
+
+        let err = input.parse::<i32>(&input.text()[15..16]).unwrap_err();
+        assert_eq!((err.line, err.column, err.token.as_str()), (2, 6, "x"));
+        assert_eq!(err.source_line, "down x");
+    }
+
+    #[test]
+    fn reports_missing_tokens() {
+        let input = Input::new(2, "forward 5\ndown");
+        let line = input.lines().nth(1).unwrap();
+        let err = input.expect(line.split(' ').nth(1), line, "a number").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 5));
+        assert_eq!(err.diagnostic(), "error: expected a number\n --> day 2, line 2, column 5\n  |\n2 | down\n  |     ^");
+    }
+}
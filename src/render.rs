@@ -0,0 +1,289 @@
+//! Images of the puzzle states, as PPM or PNG frames, or animated GIFs.
+//!
+//! | Day | Frames                                                  |
+//! |-----|---------------------------------------------------------|
+//! | 11  | Octopus energy at each step, until they all flash       |
+//! | 13  | The dots on the paper before folding, then at each fold |
+//! | 15  | The safest path being drawn over the risk levels        |
+//! | 20  | The image at each of the 50 enhancements                |
+//! | 25  | Both herds at each step, until they stop moving         |
+//!
+//! Day 5 is a still image instead, the [`heatmap`] of its vents.
+
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::ops::ControlFlow;
+use std::path::Path;
+
+use crate::days::{Cavern, Cucumber, Heatmap, Herds, Image, Octopuses, Paper};
+use crate::grid::Grid;
+use crate::parse::ParseError;
+use crate::sim::{Runner, Simulation};
+
+pub type Rgb = [u8; 3];
+
+const BACKGROUND: Rgb = [16, 16, 32];
+
+/// Stops simulations that never settle, like day 11 on inputs where the octopuses never synchronize
+const MAX_STEPS: usize = 1000;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+    width: usize,
+    height: usize,
+    pixels: Vec<Rgb>,
+}
+
+impl Frame {
+    /// Draws each cell as a `scale` by `scale` square
+    pub fn from_grid<T>(grid: &Grid<T>, scale: usize, mut color: impl FnMut(&T) -> Rgb) -> Self {
+        let (width, height) = (grid.width() * scale, grid.height() * scale);
+        let colors: Vec<Rgb> = grid.cells().iter().map(&mut color).collect();
+        let pixels = (0..width * height).map(|i| colors[(i / width / scale) * grid.width() + i % width / scale]);
+        Frame { width, height, pixels: pixels.collect() }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Grows the frame to the given size, filling around it with `fill`, offset by `(x, y)`
+    fn pad(&self, width: usize, height: usize, (x, y): (usize, usize), fill: Rgb) -> Frame {
+        let mut pixels = vec![fill; width * height];
+        for (row, line) in self.pixels.chunks(self.width).enumerate() {
+            pixels[(y + row) * width + x..][..self.width].copy_from_slice(line);
+        }
+        Frame { width, height, pixels }
+    }
+
+    fn rgb(&self) -> Vec<u8> {
+        self.pixels.concat()
+    }
+
+    /// Binary PPM (`P6`) encoding of the frame
+    pub fn to_ppm(&self) -> Vec<u8> {
+        let mut buf = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+        buf.extend(self.rgb());
+        buf
+    }
+
+    pub fn to_png(&self) -> Result<Vec<u8>, png::EncodingError> {
+        let mut buf = vec![];
+        let mut encoder = png::Encoder::new(&mut buf, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header()?.write_image_data(&self.rgb())?;
+        Ok(buf)
+    }
+}
+
+/// How to write an animation to disk
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum Format {
+    /// A single animated GIF, `day<N>.gif`
+    Gif,
+    /// One numbered PPM image per frame, in a `day<N>` directory
+    Ppm,
+    /// One numbered PNG image per frame, in a `day<N>` directory
+    Png,
+}
+
+/// Writes the frames of a day to `dir`, returning the path written
+pub fn save(frames: &[Frame], dir: &Path, day: u8, format: Format) -> Result<std::path::PathBuf, Box<dyn Error>> {
+    fs::create_dir_all(dir)?;
+    if format == Format::Gif {
+        let path = dir.join(format!("day{}.gif", day));
+        write_gif(frames, &path, 10)?;
+        return Ok(path);
+    }
+    let path = dir.join(format!("day{}", day));
+    fs::create_dir_all(&path)?;
+    for (i, frame) in frames.iter().enumerate() {
+        let (data, extension) = match format {
+            Format::Png => (frame.to_png()?, "png"),
+            _ => (frame.to_ppm(), "ppm"),
+        };
+        fs::write(path.join(format!("frame-{:04}.{}", i, extension)), data)?;
+    }
+    Ok(path)
+}
+
+/// Writes an animated GIF looping over the frames, shown `delay` hundredths of a second each. Smaller frames are
+/// drawn at the top left of the largest one.
+pub fn write_gif(frames: &[Frame], path: &Path, delay: u16) -> Result<(), Box<dyn Error>> {
+    let width = frames.iter().map(Frame::width).max().unwrap_or(1);
+    let height = frames.iter().map(Frame::height).max().unwrap_or(1);
+    if width > u16::MAX as usize || height > u16::MAX as usize {
+        return Err(format!("{}x{} is too large for a GIF", width, height).into());
+    }
+    let mut encoder = gif::Encoder::new(BufWriter::new(File::create(path)?), width as u16, height as u16, &[])?;
+    encoder.set_repeat(gif::Repeat::Infinite)?;
+    for frame in frames {
+        let frame = frame.pad(width, height, (0, 0), BACKGROUND);
+        let mut gif_frame = gif::Frame::from_rgb_speed(width as u16, height as u16, &frame.rgb(), 10);
+        gif_frame.delay = delay;
+        encoder.write_frame(&gif_frame)?;
+    }
+    Ok(())
+}
+
+/// Frames showing how the puzzle of `day` plays out on `input`, if that day can be rendered
+pub fn animate(day: u8, input: &str) -> Result<Option<Vec<Frame>>, ParseError> {
+    Ok(Some(match day {
+        11 => octopuses(Octopuses::parse(input)?),
+        13 => paper(&Paper::parse(input)?),
+        15 => cavern(&Cavern::parse(input)?),
+        20 => image(Image::parse(input)?),
+        25 => herds(Herds::parse(input)?),
+        _ => return Ok(None),
+    }))
+}
+
+/// Points covered by more vent lines are brighter, from dark red to yellow
+pub fn heatmap(heatmap: &Heatmap, scale: usize) -> Frame {
+    let max = heatmap.max().max(1) as u32;
+    Frame::from_grid(&heatmap.counts, scale, |&count| match count as u32 * 255 / max {
+        0 => BACKGROUND,
+        heat => [128 + (heat / 2) as u8, heat as u8, 0],
+    })
+}
+
+fn octopuses(octopuses: Octopuses) -> Vec<Frame> {
+    let color = |&energy: &u8| match energy {
+        // Just flashed
+        0 => [255, 250, 200],
+        e => [8 + e * 6, 16 + e * 10, 48 + e * 16],
+    };
+    let size = octopuses.snapshot().len();
+    let mut frames = vec![Frame::from_grid(&octopuses.snapshot(), 8, color)];
+    Runner::new(octopuses)
+        .observe(|o, _| {
+            frames.push(Frame::from_grid(&o.snapshot(), 8, color));
+            ControlFlow::Continue(())
+        })
+        .run_until(|o, &flashed| flashed == size || o.generation() >= MAX_STEPS);
+    frames
+}
+
+fn paper(paper: &Paper) -> Vec<Frame> {
+    paper
+        .fold_states()
+        .iter()
+        .map(|dots| Frame::from_grid(dots, 1, |&dot| if dot { [255, 255, 255] } else { BACKGROUND }))
+        .collect()
+}
+
+/// The risk levels shaded from light to dark, then the path appearing over about 60 frames
+fn cavern(cavern: &Cavern) -> Vec<Frame> {
+    let risks = cavern.risks().map(|&r| Some(r));
+    let path = cavern.safest_path();
+    let color = |cell: &Option<u8>| match cell {
+        Some(risk) => [255 - risk * 24; 3],
+        None => [220, 40, 40],
+    };
+    let scale = (600 / risks.width().max(risks.height())).max(1);
+    let mut grid = risks.clone();
+    let mut frames = vec![Frame::from_grid(&grid, scale, color)];
+    for chunk in path.chunks(path.len().div_ceil(60)) {
+        for &pos in chunk {
+            grid[pos] = None;
+        }
+        frames.push(Frame::from_grid(&grid, scale, color));
+    }
+    frames
+}
+
+/// The image grows by a pixel on each side at each step, earlier frames are centered in the final size and surrounded
+/// by the infinite color of their step
+fn image(image: Image) -> Vec<Frame> {
+    let color = |&lit: &bool| if lit { [255, 255, 255] } else { BACKGROUND };
+    let scale = 2;
+    let mut states = vec![(image.snapshot(), image.infinite_color())];
+    Runner::new(image)
+        .observe(|i, _| {
+            states.push((i.snapshot(), i.infinite_color()));
+            ControlFlow::Continue(())
+        })
+        .run(50);
+    let last = &states[states.len() - 1].0;
+    let (width, height) = (last.width() * scale, last.height() * scale);
+    states
+        .iter()
+        .map(|(pixels, infinite)| {
+            let frame = Frame::from_grid(pixels, scale, color);
+            let offset = ((width - frame.width()) / 2, (height - frame.height()) / 2);
+            frame.pad(width, height, offset, color(infinite))
+        })
+        .collect()
+}
+
+fn herds(herds: Herds) -> Vec<Frame> {
+    let color = |cucumber: &Cucumber| match cucumber {
+        Cucumber::East => [240, 140, 40],
+        Cucumber::South => [40, 180, 200],
+        Cucumber::None => BACKGROUND,
+    };
+    let mut frames = vec![Frame::from_grid(&herds.snapshot(), 3, color)];
+    Runner::new(herds)
+        .observe(|h, _| {
+            frames.push(Frame::from_grid(&h.snapshot(), 3, color));
+            ControlFlow::Continue(())
+        })
+        .run_until(|h, &moved| moved == 0 || h.generation() >= MAX_STEPS);
+    frames
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scales_and_encodes_frames() {
+        let grid = Grid::from_rows(vec![vec![true, false], vec![false, false]]);
+        let frame = Frame::from_grid(&grid, 2, |&b| if b { [255, 0, 0] } else { [0, 0, 0] });
+        assert_eq!((frame.width(), frame.height()), (4, 4));
+        let ppm = frame.to_ppm();
+        assert!(ppm.starts_with(b"P6\n4 4\n255\n"));
+        let pixels = &ppm[11..];
+        assert_eq!(pixels.len(), 4 * 4 * 3);
+        assert_eq!(&pixels[..6], &[255, 0, 0, 255, 0, 0]);
+        assert_eq!(&pixels[4 * 3 + 3..][..3], &[255, 0, 0]);
+        assert_eq!(&pixels[2 * 4 * 3..][..3], &[0, 0, 0]);
+        assert!(frame.to_png().unwrap().starts_with(b"\x89PNG"));
+    }
+
+    #[test]
+    fn animates_simulation_days() {
+        let input = "5483143223\n2745854711\n5264556173\n6141336146\n6357385478\n\
+                     4167524645\n2176841721\n6882881134\n4846848554\n5283751526";
+        // The initial state, then every step until they all flash at step 195
+        assert_eq!(animate(11, input).unwrap().unwrap().len(), 196);
+        assert!(animate(1, "199").unwrap().is_none());
+
+        let dir = std::env::temp_dir().join(format!("aoc-render-{}", std::process::id()));
+        let frames = animate(13, "6,10\n0,14\n9,10\n0,3\n\nfold along y=7\nfold along x=5").unwrap().unwrap();
+        assert_eq!(frames.len(), 3);
+        let gif = save(&frames, &dir, 13, Format::Gif).unwrap();
+        assert!(fs::read(gif).unwrap().starts_with(b"GIF89a"));
+        let frames_dir = save(&frames, &dir, 13, Format::Ppm).unwrap();
+        assert_eq!(fs::read_dir(frames_dir).unwrap().count(), 3);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn colors_heatmaps() {
+        let vents = crate::days::Vents::parse("0,0 -> 2,0\n1,0 -> 1,1").unwrap();
+        let frame = heatmap(&vents.heatmap(true, None).unwrap(), 2);
+        assert_eq!((frame.width(), frame.height()), (6, 4));
+        let ppm = frame.to_ppm();
+        let pixel = |x: usize, y: usize| &ppm[11 + (y * 6 + x) * 3..][..3];
+        assert_eq!(pixel(0, 0), [191, 127, 0]);
+        assert_eq!(pixel(2, 0), [255, 255, 0]);
+        assert_eq!(pixel(0, 2), BACKGROUND);
+    }
+}
@@ -0,0 +1,475 @@
+//! Exact intersections of line segments of any slope, with a Bentley–Ottmann sweep.
+//!
+//! Segments lying on the same line are merged first: the stretches covered by several of them are found by sorting
+//! their endpoints, and each run of touching or overlapping segments becomes a single piece. The sweep then only deals
+//! with pieces on different lines, which meet at single points. Those points are computed exactly as fractions, so
+//! nothing is rasterized and coordinates can use the whole `i32` range.
+
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fmt::{self, Display};
+use std::ops::Range;
+
+use fxhash::FxHashMap;
+
+pub type Coord = (i32, i32);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Segment {
+    pub start: Coord,
+    pub end: Coord,
+}
+
+impl Segment {
+    pub fn new(start: Coord, end: Coord) -> Self {
+        Segment { start, end }
+    }
+
+    /// Both ends ordered by x then y, the order of the sweep
+    fn ordered(&self) -> (Coord, Coord) {
+        if self.start <= self.end {
+            (self.start, self.end)
+        } else {
+            (self.end, self.start)
+        }
+    }
+}
+
+/// A point with rational coordinates `x / d` and `y / d`, in lowest terms
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Point {
+    x: i128,
+    y: i128,
+    d: i128,
+}
+
+impl Point {
+    fn new(x: i128, y: i128, d: i128) -> Self {
+        let sign = d.signum();
+        let g = gcd(gcd(x.unsigned_abs(), y.unsigned_abs()), d.unsigned_abs()) as i128;
+        Point { x: sign * x / g, y: sign * y / g, d: sign * d / g }
+    }
+
+    fn int((x, y): Coord) -> Self {
+        Point { x: x as i128, y: y as i128, d: 1 }
+    }
+
+    /// Numerator and denominator of the x coordinate, in lowest terms
+    pub fn x(&self) -> (i128, i128) {
+        reduce(self.x, self.d)
+    }
+
+    pub fn y(&self) -> (i128, i128) {
+        reduce(self.y, self.d)
+    }
+
+    pub fn as_coord(&self) -> Option<Coord> {
+        (self.d == 1).then_some((self.x as i32, self.y as i32))
+    }
+}
+
+impl From<Coord> for Point {
+    fn from(coord: Coord) -> Self {
+        Point::int(coord)
+    }
+}
+
+impl Ord for Point {
+    fn cmp(&self, other: &Self) -> Ordering {
+        cmp_products(self.x, other.d, other.x, self.d).then_with(|| cmp_products(self.y, other.d, other.y, self.d))
+    }
+}
+
+impl PartialOrd for Point {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fraction = |(n, d): (i128, i128)| if d == 1 { n.to_string() } else { format!("{}/{}", n, d) };
+        write!(f, "{},{}", fraction(self.x()), fraction(self.y()))
+    }
+}
+
+/// A point where segments of different lines meet, with every segment going through it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Crossing {
+    pub at: Point,
+    pub segments: Vec<usize>,
+}
+
+/// Part of a line covered by several segments, a single point when they only touch. The segments are the ones of the
+/// line overlapping it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Stretch {
+    pub from: Coord,
+    pub to: Coord,
+    pub segments: Vec<usize>,
+}
+
+impl Stretch {
+    fn contains(&self, p: &Point) -> bool {
+        Point::int(self.from) <= *p && *p <= Point::int(self.to)
+    }
+
+    fn lattice_points(&self) -> u64 {
+        let (dx, dy) = (self.to.0 as i64 - self.from.0 as i64, self.to.1 as i64 - self.from.1 as i64);
+        gcd(dx.unsigned_abs() as u128, dy.unsigned_abs() as u128) as u64 + 1
+    }
+}
+
+/// Where segments overlap. A point can be both a crossing and on a stretch, when other lines cross a stretch.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Intersections {
+    /// In sweep order, by x then y
+    pub crossings: Vec<Crossing>,
+    pub stretches: Vec<Stretch>,
+    lattice_points: u64,
+}
+
+impl Intersections {
+    /// The number of points with integer coordinates covered by at least two segments
+    pub fn lattice_points(&self) -> u64 {
+        self.lattice_points
+    }
+}
+
+/// Segments of the same line covering a connected part of it, or a single segment of length zero
+struct Piece {
+    start: Coord,
+    end: Coord,
+    members: Vec<usize>,
+    /// The stretches of the line within the piece
+    stretches: Range<usize>,
+}
+
+impl Piece {
+    fn delta(&self) -> (i128, i128) {
+        (self.end.0 as i128 - self.start.0 as i128, self.end.1 as i128 - self.start.1 as i128)
+    }
+
+    /// Whether the piece is below the sweep line's position `p`, goes through it or is above it. Only pieces crossing
+    /// the sweep line are asked, vertical ones then always go through it.
+    fn side(&self, p: &Point) -> Ordering {
+        let (dx, dy) = self.delta();
+        if dx == 0 {
+            return if (self.end.1 as i128) * p.d < p.y { Ordering::Less } else { Ordering::Equal };
+        }
+        let (rx, ry) = (p.x - self.start.0 as i128 * p.d, p.y - self.start.1 as i128 * p.d);
+        // Positive cross product of the piece's direction and `p`, so `p` is above it
+        cmp_products(dx, ry, dy, rx).reverse()
+    }
+
+    /// Order of pieces going through the same point, just after it: by slope, vertical ones last
+    fn cmp_slope(&self, other: &Piece) -> Ordering {
+        let ((dx, dy), (odx, ody)) = (self.delta(), other.delta());
+        (dx == 0).cmp(&(odx == 0)).then_with(|| (dy * odx).cmp(&(ody * dx)))
+    }
+
+    fn intersection(&self, other: &Piece) -> Option<Point> {
+        let (r, s) = (self.delta(), other.delta());
+        let q = (other.start.0 as i128 - self.start.0 as i128, other.start.1 as i128 - self.start.1 as i128);
+        let (mut den, mut t, mut u) = (cross(r, s), cross(q, s), cross(q, r));
+        if den == 0 {
+            return None;
+        }
+        if den < 0 {
+            (den, t, u) = (-den, -t, -u);
+        }
+        if t < 0 || t > den || u < 0 || u > den {
+            return None;
+        }
+        Some(Point::new(self.start.0 as i128 * den + t * r.0, self.start.1 as i128 * den + t * r.1, den))
+    }
+}
+
+pub fn intersections(segments: &[Segment]) -> Intersections {
+    let ordered: Vec<_> = segments.iter().map(Segment::ordered).collect();
+    let mut pieces = vec![];
+    let mut lines: FxHashMap<_, Vec<usize>> = FxHashMap::default();
+    for (i, &(start, end)) in ordered.iter().enumerate() {
+        if start == end {
+            pieces.push(Piece { start, end, members: vec![i], stretches: 0..0 });
+            continue;
+        }
+        let (dx, dy) = (end.0 as i128 - start.0 as i128, end.1 as i128 - start.1 as i128);
+        let g = gcd(dx.unsigned_abs(), dy.unsigned_abs()) as i128;
+        let (dx, dy) = (dx / g, dy / g);
+        lines.entry((dx, dy, dx * start.1 as i128 - dy * start.0 as i128)).or_default().push(i);
+    }
+    let mut lines: Vec<_> = lines.into_iter().collect();
+    lines.sort_unstable();
+    let mut stretches = vec![];
+    for (_, line) in lines {
+        merge_line(&ordered, &line, &mut pieces, &mut stretches);
+    }
+    let lattice_points = stretches.iter().map(Stretch::lattice_points).sum();
+    let mut result = Intersections { crossings: vec![], stretches, lattice_points };
+    sweep(&ordered, &pieces, &mut result);
+    result
+}
+
+/// Splits the segments of a line into pieces, and finds the stretches covered by several of them. Ends are sorted
+/// starts first, so that segments touching at a point overlap there.
+fn merge_line(ordered: &[(Coord, Coord)], line: &[usize], pieces: &mut Vec<Piece>, stretches: &mut Vec<Stretch>) {
+    let mut ends: Vec<_> = line.iter().flat_map(|&i| [(ordered[i].0, false, i), (ordered[i].1, true, i)]).collect();
+    ends.sort_unstable();
+    let mut active: Vec<usize> = vec![];
+    let (mut piece, mut stretch) = (Piece { start: (0, 0), end: (0, 0), members: vec![], stretches: 0..0 }, vec![]);
+    let mut stretch_from = (0, 0);
+    for (p, is_end, i) in ends {
+        if !is_end {
+            if active.is_empty() {
+                piece.start = p;
+                piece.stretches.start = stretches.len();
+            }
+            piece.members.push(i);
+            active.push(i);
+            match active.len() {
+                2 => (stretch_from, stretch) = (p, active.clone()),
+                n if n > 2 => stretch.push(i),
+                _ => {}
+            }
+            continue;
+        }
+        active.retain(|&j| j != i);
+        if active.len() == 1 {
+            stretch.sort_unstable();
+            stretches.push(Stretch { from: stretch_from, to: p, segments: std::mem::take(&mut stretch) });
+        }
+        if active.is_empty() {
+            piece.members.sort_unstable();
+            let stretches = piece.stretches.start..stretches.len();
+            pieces.push(Piece { end: p, members: std::mem::take(&mut piece.members), stretches, ..piece });
+        }
+    }
+}
+
+/// Moves a vertical line from left to right, stopping at the ends of pieces and at the points where they meet. The
+/// status holds the pieces crossing the line from bottom to top, only neighbours there can meet next. It is a sorted
+/// vector rather than a tree: the comparison depends on the line's position, and moving a few pointers around is cheap.
+fn sweep(ordered: &[(Coord, Coord)], pieces: &[Piece], result: &mut Intersections) {
+    let mut events: BTreeMap<Point, Vec<usize>> = BTreeMap::new();
+    for (i, piece) in pieces.iter().enumerate() {
+        events.entry(Point::int(piece.start)).or_default().push(i);
+        events.entry(Point::int(piece.end)).or_default();
+    }
+    let mut status: Vec<usize> = vec![];
+    while let Some((p, starting)) = events.pop_first() {
+        let below = status.partition_point(|&s| pieces[s].side(&p) == Ordering::Less);
+        let through = status[below..].partition_point(|&s| pieces[s].side(&p) == Ordering::Equal);
+        let going: Vec<usize> = status.drain(below..below + through).collect();
+
+        if going.len() + starting.len() > 1 {
+            let at_p = going.iter().chain(&starting).map(|&s| &pieces[s]);
+            let mut segments: Vec<usize> = at_p
+                .clone()
+                .flat_map(|piece| piece.members.iter().copied())
+                .filter(|&m| Point::int(ordered[m].0) <= p && p <= Point::int(ordered[m].1))
+                .collect();
+            segments.sort_unstable();
+            if p.d == 1 {
+                // The point was already counted once for each stretch it is on
+                let stretches = &result.stretches;
+                let on_stretch = |piece: &&Piece| stretches[piece.stretches.clone()].iter().any(|s| s.contains(&p));
+                match at_p.filter(on_stretch).count() {
+                    0 => result.lattice_points += 1,
+                    n => result.lattice_points -= n as u64 - 1,
+                }
+            }
+            result.crossings.push(Crossing { at: p, segments });
+        }
+
+        let mut continuing: Vec<usize> = going
+            .into_iter()
+            .filter(|&s| Point::int(pieces[s].end) != p)
+            .chain(starting.into_iter().filter(|&s| pieces[s].start != pieces[s].end))
+            .collect();
+        continuing.sort_by(|&a, &b| pieces[a].cmp_slope(&pieces[b]));
+        let n = continuing.len();
+        status.splice(below..below, continuing);
+
+        let mut check = |a: usize, b: usize| {
+            if let Some(q) = pieces[a].intersection(&pieces[b]).filter(|q| *q > p) {
+                events.entry(q).or_default();
+            }
+        };
+        if n == 0 {
+            if below > 0 && below < status.len() {
+                check(status[below - 1], status[below]);
+            }
+        } else {
+            if below > 0 {
+                check(status[below - 1], status[below]);
+            }
+            if below + n < status.len() {
+                check(status[below + n - 1], status[below + n]);
+            }
+        }
+    }
+}
+
+fn cross((ax, ay): (i128, i128), (bx, by): (i128, i128)) -> i128 {
+    ax * by - ay * bx
+}
+
+pub(crate) fn gcd(mut a: u128, mut b: u128) -> u128 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+fn reduce(n: i128, d: i128) -> (i128, i128) {
+    let g = gcd(n.unsigned_abs(), d.unsigned_abs()) as i128;
+    (n / g, d / g)
+}
+
+/// Compares `a * b` with `c * d`, which can take up to 256 bits
+fn cmp_products(a: i128, b: i128, c: i128, d: i128) -> Ordering {
+    let (left, right) = (wide_mul(a, b), wide_mul(c, d));
+    match (left.0, right.0) {
+        (false, false) => (left.1, left.2).cmp(&(right.1, right.2)),
+        (true, true) => (right.1, right.2).cmp(&(left.1, left.2)),
+        (negative, _) => if negative { Ordering::Less } else { Ordering::Greater },
+    }
+}
+
+/// Sign and magnitude of `a * b`, the magnitude as its high and low 128 bits
+fn wide_mul(a: i128, b: i128) -> (bool, u128, u128) {
+    let negative = (a < 0) != (b < 0) && a != 0 && b != 0;
+    let (a, b) = (a.unsigned_abs(), b.unsigned_abs());
+    let low = |n: u128| n & u64::MAX as u128;
+    let (a1, a0, b1, b0) = (a >> 64, low(a), b >> 64, low(b));
+    let (middle, carry) = (a1 * b0).overflowing_add(a0 * b1);
+    let (lo, lo_carry) = (a0 * b0).overflowing_add(middle << 64);
+    let hi = a1 * b1 + (middle >> 64) + ((carry as u128) << 64) + lo_carry as u128;
+    (negative, hi, lo)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fxhash::FxHashSet;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    fn segment(s: &str) -> Segment {
+        let n: Vec<i32> = s.split([',', ' ']).filter_map(|n| n.parse().ok()).collect();
+        Segment::new((n[0], n[1]), (n[2], n[3]))
+    }
+
+    /// Every pair of segments on different lines, and every lattice point of the bounding box
+    fn naive(segments: &[Segment]) -> (FxHashSet<Point>, u64) {
+        let pieces: Vec<_> = segments
+            .iter()
+            .map(|s| {
+                let (start, end) = s.ordered();
+                Piece { start, end, members: vec![], stretches: 0..0 }
+            })
+            .collect();
+        let contains = |piece: &Piece, p: &Point| {
+            let (dx, dy) = piece.delta();
+            let on_line = cmp_products(dx, p.y - piece.start.1 as i128 * p.d, dy, p.x - piece.start.0 as i128 * p.d);
+            on_line == Ordering::Equal && Point::int(piece.start) <= *p && *p <= Point::int(piece.end)
+        };
+        let mut crossings = FxHashSet::default();
+        for (i, a) in pieces.iter().enumerate() {
+            for b in &pieces[i + 1..] {
+                let point = if a.start == a.end { Some(Point::int(a.start)).filter(|p| contains(b, p)) } else {
+                    let single = Some(Point::int(b.start)).filter(|p| b.start == b.end && contains(a, p));
+                    single.or_else(|| a.intersection(b))
+                };
+                crossings.extend(point);
+            }
+        }
+        let (min, max) = (-20, 20);
+        let lattice = (min..=max)
+            .flat_map(|x| (min..=max).map(move |y| Point::int((x, y))))
+            .filter(|p| pieces.iter().filter(|piece| contains(piece, p)).count() > 1)
+            .count();
+        (crossings, lattice as u64)
+    }
+
+    #[test]
+    fn puzzle_example() {
+        let segments: Vec<_> = "0,9 -> 5,9|8,0 -> 0,8|9,4 -> 3,4|2,2 -> 2,1|7,0 -> 7,4|6,4 -> 2,0|0,9 -> 2,9|\
+                                3,4 -> 1,4|0,0 -> 8,8|5,5 -> 8,2"
+            .split('|')
+            .map(segment)
+            .collect();
+        let result = intersections(&segments);
+        assert_eq!(result.lattice_points(), 12);
+        let stretches: Vec<_> = result.stretches.iter().map(|s| (s.from, s.to, s.segments.clone())).collect();
+        assert_eq!(stretches, [((3, 4), (3, 4), vec![2, 7]), ((0, 9), (2, 9), vec![0, 6])]);
+        assert!(result.crossings.contains(&Crossing { at: Point::int((7, 4)), segments: vec![2, 4] }));
+    }
+
+    #[test]
+    fn rational_crossings() {
+        let segments = [segment("0,0 1,1"), segment("0,1 1,0"), segment("0,0 3,1"), segment("1,0 1,5")];
+        let result = intersections(&segments);
+        let crossings: Vec<_> = result.crossings.iter().map(|c| (c.at.to_string(), c.segments.clone())).collect();
+        assert_eq!(crossings, [
+            ("0,0".to_string(), vec![0, 2]),
+            ("1/2,1/2".to_string(), vec![0, 1]),
+            ("3/4,1/4".to_string(), vec![1, 2]),
+            ("1,0".to_string(), vec![1, 3]),
+            ("1,1/3".to_string(), vec![2, 3]),
+            ("1,1".to_string(), vec![0, 3]),
+        ]);
+        assert_eq!(result.crossings[1].at.x(), (1, 2));
+        assert_eq!(result.lattice_points(), 3);
+    }
+
+    #[test]
+    fn large_coordinates() {
+        let max = i32::MAX;
+        let segments = [
+            Segment::new((-max, -max), (max, max)),
+            Segment::new((-max, max), (max, i32::MIN)),
+            Segment::new((0, 0), (max, max)),
+            Segment::new((max - 1, -max), (max - 1, max)),
+        ];
+        let result = intersections(&segments);
+        let crossings: Vec<_> = result.crossings.iter().map(|c| (c.at.to_string(), c.segments.clone())).collect();
+        assert_eq!(crossings, [
+            ("-2147483647/8589934589,-2147483647/8589934589".to_string(), vec![0, 1]),
+            ("2147483646,-9223372028264841217/4294967294".to_string(), vec![1, 3]),
+            ("2147483646,2147483646".to_string(), vec![0, 2, 3]),
+        ]);
+        assert_eq!(result.stretches.len(), 1);
+        assert_eq!(result.lattice_points(), max as u64 + 1);
+    }
+
+    #[test]
+    fn matches_naive_on_random_segments() {
+        let mut rng = ChaCha8Rng::seed_from_u64(5);
+        for round in 0..300 {
+            let n = rng.gen_range(1..12);
+            let mut segments = vec![];
+            for _ in 0..n {
+                let start = (rng.gen_range(-6..=6), rng.gen_range(-6..=6));
+                let end = match rng.gen_range(0..4) {
+                    // Lines sharing directions and points, so that they overlap and meet at the same places
+                    0 => start,
+                    1 => (start.0, rng.gen_range(-6..=6)),
+                    2 => {
+                        let k = rng.gen_range(-3..=3);
+                        (start.0 + k, start.1 + k * rng.gen_range(-1..=1))
+                    }
+                    _ => (rng.gen_range(-6..=6), rng.gen_range(-6..=6)),
+                };
+                segments.push(Segment::new(start, end));
+            }
+            let result = intersections(&segments);
+            let (crossings, lattice) = naive(&segments);
+            let found: FxHashSet<_> = result.crossings.iter().map(|c| c.at).collect();
+            assert_eq!(found, crossings, "round {}: {:?}", round, segments);
+            assert_eq!(found.len(), result.crossings.len(), "round {}", round);
+            assert_eq!(result.lattice_points(), lattice, "round {}: {:?}", round, segments);
+        }
+    }
+}
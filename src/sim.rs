@@ -0,0 +1,148 @@
+use std::ops::ControlFlow;
+
+/// A puzzle state evolving one generation at a time, like the octopuses of day 11 or the image of day 20. See
+/// [`crate::days`] for the ones implemented.
+pub trait Simulation {
+    /// What a step reports, like the number of octopuses that flashed
+    type Step;
+    /// Copy of the state, as kept in a history
+    type Snapshot;
+
+    /// Runs a single generation
+    fn step(&mut self) -> Self::Step;
+
+    /// Number of steps run so far
+    fn generation(&self) -> usize;
+
+    fn snapshot(&self) -> Self::Snapshot;
+}
+
+type Observer<'a, S> = Box<dyn FnMut(&S, &<S as Simulation>::Step) -> ControlFlow<()> + 'a>;
+
+/// Steps a simulation, calling observers after each generation. Any observer can stop the run.
+pub struct Runner<'a, S: Simulation> {
+    sim: S,
+    observers: Vec<Observer<'a, S>>,
+}
+
+impl<'a, S: Simulation> Runner<'a, S> {
+    pub fn new(sim: S) -> Self {
+        Runner { sim, observers: vec![] }
+    }
+
+    /// Calls `observer` with the state and what the step reported, after every step
+    pub fn observe(mut self, observer: impl FnMut(&S, &S::Step) -> ControlFlow<()> + 'a) -> Self {
+        self.observers.push(Box::new(observer));
+        self
+    }
+
+    /// Stores a snapshot of the state after every step in `history`
+    pub fn record(self, history: &'a mut Vec<S::Snapshot>) -> Self {
+        self.observe(move |sim, _| {
+            history.push(sim.snapshot());
+            ControlFlow::Continue(())
+        })
+    }
+
+    /// Runs one step and notifies every observer, breaking if one of them did
+    pub fn step(&mut self) -> ControlFlow<()> {
+        let step = self.sim.step();
+        self.notify(&step)
+    }
+
+    fn notify(&mut self, step: &S::Step) -> ControlFlow<()> {
+        let mut flow = ControlFlow::Continue(());
+        for observer in &mut self.observers {
+            if observer(&self.sim, step).is_break() {
+                flow = ControlFlow::Break(());
+            }
+        }
+        flow
+    }
+
+    /// Runs `steps` steps, or until an observer stops it, returning the generation reached
+    pub fn run(&mut self, steps: usize) -> usize {
+        for _ in 0..steps {
+            if self.step().is_break() {
+                break;
+            }
+        }
+        self.sim.generation()
+    }
+
+    /// Runs until `stop` holds after a step, or an observer stops it, returning the generation reached
+    pub fn run_until(&mut self, mut stop: impl FnMut(&S, &S::Step) -> bool) -> usize {
+        loop {
+            let step = self.sim.step();
+            if self.notify(&step).is_break() || stop(&self.sim, &step) {
+                return self.sim.generation();
+            }
+        }
+    }
+
+    pub fn sim(&self) -> &S {
+        &self.sim
+    }
+
+    pub fn into_inner(self) -> S {
+        self.sim
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::days::{Herds, Octopuses, School};
+
+    static OCTOPUSES: &str = "5483143223
+2745854711
+5264556173
+6141336146
+6357385478
+4167524645
+2176841721
+6882881134
+4846848554
+5283751526";
+
+    #[test]
+    fn stops_on_predicate() {
+        let octopuses = Octopuses::parse(OCTOPUSES).unwrap();
+        let size = octopuses.snapshot().len();
+        assert_eq!(Runner::new(octopuses).run_until(|_, &flashed| flashed == size), 195);
+    }
+
+    #[test]
+    fn records_history() {
+        let mut history = vec![];
+        let mut flashes = 0;
+        let mut runner = Runner::new(Octopuses::parse(OCTOPUSES).unwrap())
+            .record(&mut history)
+            .observe(|_, &flashed| {
+                flashes += flashed;
+                ControlFlow::Continue(())
+            });
+        assert_eq!(runner.run(10), 10);
+        drop(runner);
+        assert_eq!(flashes, 204);
+        assert_eq!(history.len(), 10);
+        assert_eq!(history[1].render(|&o| char::from(b'0' + o)).lines().next(), Some("8807476555"));
+    }
+
+    #[test]
+    fn observers_can_stop_the_run() {
+        let mut runner = Runner::new(School::parse("3,4,3,1,2").unwrap()).observe(|school, _| {
+            if school.snapshot().iter().sum::<usize>() >= 26 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        });
+        assert_eq!(runner.run(80), 18);
+        assert_eq!(runner.sim().generation(), 18);
+
+        let herds = "v...>>.vv>\n.vv>>.vv..\n>>.>v>...v\n>>v>>.>.v.\nv>v.vv.v..\n>.>>..v...\n.vv..>.>v.\nv.v..>>v.v\n....v..v.>";
+        let mut runner = Runner::new(Herds::parse(herds).unwrap());
+        assert_eq!(runner.run_until(|_, &moved| moved == 0), 58);
+    }
+}
@@ -0,0 +1,194 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::days::Solver;
+use crate::parse::ParseError;
+
+/// Identifies an input in the answers file. FNV-1a, so it stays stable across builds and platforms.
+pub fn fingerprint(input: &str) -> String {
+    let hash = input
+        .trim_end_matches('\n')
+        .bytes()
+        .fold(0xcbf29ce484222325u64, |hash, b| (hash ^ b as u64).wrapping_mul(0x100000001b3));
+    format!("{:016x}", hash)
+}
+
+/// Known-correct answers, stored as TOML:
+///
+/// ```toml
+/// [day5.3a6c1bd42e8f5d07]
+/// part1 = "5"
+/// part2 = "12"
+/// ```
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct Answers {
+    // day -> input fingerprint -> part -> answer
+    days: BTreeMap<String, BTreeMap<String, BTreeMap<String, String>>>,
+}
+
+impl Answers {
+    /// Loads the answers file, a missing file being an empty database
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        match fs::read_to_string(path) {
+            Ok(text) => Answers::parse(&text),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Answers::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(Answers { days: toml::from_str(text)? })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        fs::write(path, self.to_toml()?)?;
+        Ok(())
+    }
+
+    pub fn to_toml(&self) -> Result<String, Box<dyn Error>> {
+        Ok(toml::to_string(&self.days)?)
+    }
+
+    pub fn get(&self, day: u8, fingerprint: &str, part: u8) -> Option<&str> {
+        self.days
+            .get(&format!("day{}", day))?
+            .get(fingerprint)?
+            .get(&format!("part{}", part))
+            .map(String::as_str)
+    }
+
+    pub fn insert(&mut self, day: u8, fingerprint: &str, part: u8, answer: String) {
+        self.days
+            .entry(format!("day{}", day))
+            .or_default()
+            .entry(fingerprint.to_string())
+            .or_default()
+            .insert(format!("part{}", part), answer);
+    }
+}
+
+/// A real puzzle input stored on disk
+pub struct StoredInput {
+    pub day: u8,
+    pub path: PathBuf,
+    pub text: String,
+}
+
+/// Finds inputs in `dir`, named after cargo-aoc's convention (`day5.txt`). Several inputs can be stored for the same
+/// day with a suffix, like `day5-alice.txt`.
+pub fn stored_inputs(dir: &Path) -> Result<Vec<StoredInput>, Box<dyn Error>> {
+    let mut inputs = vec![];
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_none_or(|e| e != "txt") {
+            continue;
+        }
+        let day = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .and_then(|s| s.strip_prefix("day"))
+            .and_then(|s| s.split('-').next())
+            .and_then(|s| s.parse().ok());
+        if let Some(day) = day {
+            let text = fs::read_to_string(&path)?;
+            inputs.push(StoredInput { day, path, text });
+        }
+    }
+    inputs.sort_by(|a, b| (a.day, &a.path).cmp(&(b.day, &b.path)));
+    Ok(inputs)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Correct,
+    Wrong { expected: String },
+    /// No answer recorded for this input
+    Unknown,
+    Invalid(ParseError),
+}
+
+pub struct Report {
+    pub day: u8,
+    pub part: u8,
+    pub variant: Option<&'static str>,
+    pub input: PathBuf,
+    pub fingerprint: String,
+    pub answer: Option<String>,
+    pub outcome: Outcome,
+}
+
+/// Runs every solver on every stored input for its day, checking the results against the known answers
+pub fn verify(solvers: &[Box<dyn Solver>], inputs: &[StoredInput], answers: &Answers) -> Vec<Report> {
+    let mut reports = vec![];
+    for input in inputs {
+        let fingerprint = fingerprint(&input.text);
+        for solver in solvers.iter().filter(|s| s.day() == input.day) {
+            let (answer, outcome) = match solver.run(input.text.trim_end_matches('\n')) {
+                Ok(answer) => {
+                    let outcome = match answers.get(input.day, &fingerprint, solver.part()) {
+                        Some(expected) if expected == answer => Outcome::Correct,
+                        Some(expected) => Outcome::Wrong { expected: expected.to_string() },
+                        None => Outcome::Unknown,
+                    };
+                    (Some(answer), outcome)
+                }
+                Err(e) => (None, Outcome::Invalid(e)),
+            };
+            reports.push(Report {
+                day: solver.day(),
+                part: solver.part(),
+                variant: solver.variant(),
+                input: input.path.clone(),
+                fingerprint: fingerprint.clone(),
+                answer,
+                outcome,
+            });
+        }
+    }
+    reports
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::days;
+
+    #[test]
+    fn fingerprint_ignores_trailing_newlines() {
+        assert_eq!(fingerprint(""), "cbf29ce484222325");
+        assert_eq!(fingerprint("3,4,3,1,2\n"), fingerprint("3,4,3,1,2"));
+        assert_ne!(fingerprint("3,4,3,1,2"), fingerprint("3,4,3,1,3"));
+    }
+
+    #[test]
+    fn answers_roundtrip() {
+        let mut answers = Answers::default();
+        answers.insert(6, "abc", 1, "5934".to_string());
+        answers.insert(6, "abc", 2, "26984457539".to_string());
+        let text = answers.to_toml().unwrap();
+        assert!(text.contains("[day6.abc]"));
+        let parsed = Answers::parse(&text).unwrap();
+        assert_eq!(parsed, answers);
+        assert_eq!(parsed.get(6, "abc", 2), Some("26984457539"));
+        assert_eq!(parsed.get(6, "abd", 2), None);
+    }
+
+    #[test]
+    fn reports_mismatches() {
+        let text = "3,4,3,1,2".to_string();
+        let mut answers = Answers::default();
+        answers.insert(6, &fingerprint(&text), 1, "5934".to_string());
+        answers.insert(6, &fingerprint(&text), 2, "1".to_string());
+        let inputs = [
+            StoredInput { day: 6, path: "day6.txt".into(), text },
+            StoredInput { day: 6, path: "day6-broken.txt".into(), text: "3,x".to_string() },
+        ];
+        let reports = verify(&days::registry(), &inputs, &answers);
+        let outcomes: Vec<_> = reports.iter().filter(|r| r.variant.is_none()).map(|r| (r.part, &r.outcome)).collect();
+        assert_eq!(outcomes[0], (1, &Outcome::Correct));
+        assert_eq!(outcomes[1], (2, &Outcome::Wrong { expected: "1".to_string() }));
+        assert!(matches!(outcomes[2], (1, Outcome::Invalid(_))));
+    }
+}